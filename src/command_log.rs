@@ -0,0 +1,271 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::{self, File, OpenOptions},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Number of runs kept per session before the oldest are deleted
+const MAX_RUNS: usize = 100;
+
+/// Stream of a logged command's output
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn name(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Why a command was stopped before it finished
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Interruption {
+    TimedOut { after_secs: u64 },
+    Cancelled,
+}
+
+impl Interruption {
+    pub fn icon(self) -> &'static str {
+        match self {
+            Interruption::TimedOut { .. } => "⏱️",
+            Interruption::Cancelled => "🛑",
+        }
+    }
+}
+
+impl Display for Interruption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Interruption::TimedOut { after_secs } => write!(f, "timed out after {after_secs}s"),
+            Interruption::Cancelled => f.write_str("cancelled"),
+        }
+    }
+}
+
+/// Metadata about one logged command invocation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunRecord {
+    pub id: u64,
+    /// Short name of the command (e.g. "cargo build")
+    pub command_name: String,
+    /// Full command line
+    pub command: String,
+    pub project_path: PathBuf,
+    /// Exit code, if the process exited normally
+    pub exit_code: Option<i32>,
+    /// Set if the command was killed before it finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interruption: Option<Interruption>,
+    /// Set while the command is still running and its output is being written
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub running: bool,
+    /// Seconds since the unix epoch when the command finished, or started if it is still
+    /// running
+    pub finished_at: u64,
+    pub duration_ms: u64,
+    pub stdout_lines: usize,
+    pub stderr_lines: usize,
+}
+
+impl RunRecord {
+    /// Human-readable age, e.g. "5m ago"
+    pub fn age(&self) -> String {
+        let elapsed = now().saturating_sub(self.finished_at);
+        match elapsed {
+            0..60 => format!("{elapsed}s ago"),
+            60..3600 => format!("{}m ago", elapsed / 60),
+            3600..86400 => format!("{}h ago", elapsed / 3600),
+            _ => format!("{}d ago", elapsed / 86400),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Full output of every cargo command, stored per session under `~/.ai-tools/logs`
+#[derive(Debug, Clone)]
+pub struct CommandLog {
+    root: PathBuf,
+}
+
+impl CommandLog {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn session_dir(&self, session_id: &str) -> PathBuf {
+        self.root.join(session_id)
+    }
+
+    fn output_path(&self, session_id: &str, id: u64, stream: LogStream) -> PathBuf {
+        self.session_dir(session_id)
+            .join(format!("{id}.{}.log", stream.name()))
+    }
+
+    /// Run ids in this session, oldest first
+    fn run_ids(&self, session_id: &str) -> Vec<u64> {
+        let Ok(entries) = fs::read_dir(self.session_dir(session_id)) else {
+            return vec![];
+        };
+
+        let mut ids: Vec<u64> = entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                name.to_str()?.strip_suffix(".json")?.parse().ok()
+            })
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Claim the next run id by creating its record
+    fn reserve(&self, dir: &Path, session_id: &str) -> Result<u64> {
+        let mut id = self.run_ids(session_id).last().map_or(1, |id| id + 1);
+        loop {
+            let path = dir.join(format!("{id}.json"));
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(id),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => id += 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn write_record(&self, session_id: &str, record: &RunRecord) -> Result<()> {
+        let path = self
+            .session_dir(session_id)
+            .join(format!("{}.json", record.id));
+        fs::write(path, serde_json::to_string_pretty(record)?)?;
+        Ok(())
+    }
+
+    /// Store a command's output, returning the new run's record
+    pub fn record(
+        &self,
+        session_id: &str,
+        mut record: RunRecord,
+        stdout: &str,
+        stderr: &str,
+    ) -> Result<RunRecord> {
+        let dir = self.session_dir(session_id);
+        fs::create_dir_all(&dir)?;
+
+        record.id = self.reserve(&dir, session_id)?;
+        record.stdout_lines = stdout.lines().count();
+        record.stderr_lines = stderr.lines().count();
+        fs::write(
+            self.output_path(session_id, record.id, LogStream::Stdout),
+            stdout,
+        )?;
+        fs::write(
+            self.output_path(session_id, record.id, LogStream::Stderr),
+            stderr,
+        )?;
+
+        self.finish(session_id, record)
+    }
+
+    /// Store the record of a command that is still running, returning it along with the
+    /// files its stdout and stderr are to be written to as they come in. The run can be
+    /// read while it runs, and is completed with [`CommandLog::finish`].
+    pub fn start(
+        &self,
+        session_id: &str,
+        mut record: RunRecord,
+    ) -> Result<(RunRecord, File, File)> {
+        let dir = self.session_dir(session_id);
+        fs::create_dir_all(&dir)?;
+
+        record.id = self.reserve(&dir, session_id)?;
+        record.running = true;
+        record.finished_at = now();
+        let stdout = File::create(self.output_path(session_id, record.id, LogStream::Stdout))?;
+        let stderr = File::create(self.output_path(session_id, record.id, LogStream::Stderr))?;
+        self.write_record(session_id, &record)?;
+
+        Ok((record, stdout, stderr))
+    }
+
+    /// Complete the record of a run whose output has been written, deleting the oldest
+    /// finished runs beyond the limit
+    pub fn finish(&self, session_id: &str, mut record: RunRecord) -> Result<RunRecord> {
+        record.running = false;
+        record.finished_at = now();
+        self.write_record(session_id, &record)?;
+
+        let dir = self.session_dir(session_id);
+        let ids = self.run_ids(session_id);
+        let excess = ids.len().saturating_sub(MAX_RUNS);
+        for id in &ids[..excess] {
+            let running = self
+                .get(session_id, Some(*id))
+                .is_ok_and(|record| record.running);
+            if !running {
+                self.remove(&dir, session_id, *id);
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Delete a run that was started but never ran
+    pub fn discard(&self, session_id: &str, id: u64) {
+        self.remove(&self.session_dir(session_id), session_id, id);
+    }
+
+    fn remove(&self, dir: &Path, session_id: &str, id: u64) {
+        let _ = fs::remove_file(dir.join(format!("{id}.json")));
+        for stream in [LogStream::Stdout, LogStream::Stderr] {
+            let _ = fs::remove_file(self.output_path(session_id, id, stream));
+        }
+    }
+
+    /// Most recent runs, newest first
+    pub fn list(&self, session_id: &str, limit: usize) -> Vec<RunRecord> {
+        self.run_ids(session_id)
+            .into_iter()
+            .rev()
+            .take(limit)
+            .filter_map(|id| self.get(session_id, Some(id)).ok())
+            .collect()
+    }
+
+    /// Look up a run, or the most recent run if no id is given
+    pub fn get(&self, session_id: &str, id: Option<u64>) -> Result<RunRecord> {
+        let id = match id {
+            Some(id) => id,
+            None => *self
+                .run_ids(session_id)
+                .last()
+                .ok_or_else(|| anyhow!("No cargo commands have been logged yet"))?,
+        };
+
+        let path = self.session_dir(session_id).join(format!("{id}.json"));
+        let json = fs::read_to_string(&path).map_err(|_| anyhow!("No run with id {id}"))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Full output of one stream of a run
+    pub fn read(&self, session_id: &str, id: u64, stream: LogStream) -> Result<String> {
+        let path = self.output_path(session_id, id, stream);
+        fs::read_to_string(&path).map_err(|e| anyhow!("Could not read {}: {e}", path.display()))
+    }
+}
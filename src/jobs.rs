@@ -0,0 +1,380 @@
+use crate::command_log::{CommandLog, LogStream, RunRecord};
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::finish_log;
+use crate::tools::output::strip_ansi;
+use crate::tools::process::{CommandOutput, Limits, OutputBuffer, Process};
+use anyhow::{Result, anyhow};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::Ordering},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long job_kill waits for a job to wind down before reporting
+const KILL_WAIT: Duration = Duration::from_secs(5);
+
+/// Turns a finished job's output into the result its tool would have returned
+pub type Render = Box<dyn FnOnce(&CommandOutput, &mut CargoTools) -> Result<String>>;
+
+/// The command a job runs
+#[derive(Debug)]
+pub struct JobCommand {
+    /// Short name of the command (e.g. "cargo test")
+    pub name: &'static str,
+    /// Name of a detached `cargo_run` instance
+    pub instance: Option<String>,
+    /// Full command line, for display
+    pub command_line: String,
+    pub project_path: PathBuf,
+}
+
+/// Where a job is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    /// Killed with job_kill, or timed out
+    Killed,
+}
+
+impl JobState {
+    pub fn describe(self) -> &'static str {
+        match self {
+            JobState::Running => "🔄 running",
+            JobState::Succeeded => "✅ succeeded",
+            JobState::Failed => "❌ failed",
+            JobState::Killed => "🛑 killed",
+        }
+    }
+}
+
+/// A cargo command running (or finished) in the background
+pub struct Job {
+    id: u64,
+    command: JobCommand,
+    pid: u32,
+    started: Instant,
+    /// The most recent output; the command log has all of it
+    stdout: OutputBuffer,
+    stderr: OutputBuffer,
+    /// Id of the `cargo_log` entry the output is written to, if it could be logged
+    run_id: Option<u64>,
+    limits: Limits,
+    handle: Option<JoinHandle<Result<(CommandOutput, Duration)>>>,
+    /// Set once the job's thread has been joined
+    outcome: Option<Result<Arc<CommandOutput>, String>>,
+    /// How long the job ran, once it has finished
+    duration: Option<Duration>,
+    render: Option<Render>,
+    /// The tool result, rendered the first time it is asked for
+    rendered: Option<String>,
+}
+
+impl Debug for Job {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Job")
+            .field("id", &self.id)
+            .field("command", &self.command.command_line)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+impl Job {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        self.command.name
+    }
+
+    pub fn instance(&self) -> Option<&str> {
+        self.command.instance.as_deref()
+    }
+
+    pub fn command_line(&self) -> &str {
+        &self.command.command_line
+    }
+
+    pub fn project_path(&self) -> &Path {
+        &self.command.project_path
+    }
+
+    pub fn run_id(&self) -> Option<u64> {
+        self.run_id
+    }
+
+    /// How long the job has been running, or ran for if it has finished
+    pub fn elapsed(&self) -> Duration {
+        self.duration.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn state(&self) -> JobState {
+        match &self.outcome {
+            None => JobState::Running,
+            Some(Ok(output)) if output.interruption.is_some() => JobState::Killed,
+            Some(Ok(output)) if output.success() => JobState::Succeeded,
+            Some(_) => JobState::Failed,
+        }
+    }
+
+    /// Collect the outcome if the job's thread has finished
+    fn poll(&mut self) {
+        if self.handle.as_ref().is_some_and(JoinHandle::is_finished) {
+            self.join();
+        }
+    }
+
+    fn join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.outcome = Some(match handle.join() {
+                Ok(Ok((output, duration))) => {
+                    self.duration = Some(duration);
+                    Ok(Arc::new(output))
+                }
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("job thread panicked".into()),
+            });
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    fn buffer(&self, stream: LogStream) -> &OutputBuffer {
+        match stream {
+            LogStream::Stdout => &self.stdout,
+            LogStream::Stderr => &self.stderr,
+        }
+    }
+
+    /// Output of one stream so far, after the number of lines that were dropped from its
+    /// start to save memory
+    pub fn output(&self, stream: LogStream) -> (usize, String) {
+        let buffer = self.buffer(stream).lock().unwrap();
+        (buffer.dropped_lines(), buffer.text())
+    }
+
+    /// Number of lines written to one stream so far
+    pub fn line_count(&self, stream: LogStream) -> usize {
+        self.buffer(stream).lock().unwrap().line_count()
+    }
+
+    /// Complete lines written to one stream since the byte offset `read`, advancing `read`
+    /// past them
+    pub fn lines_since(&self, stream: LogStream, read: &mut usize) -> Vec<String> {
+        self.buffer(stream).lock().unwrap().lines_since(read)
+    }
+
+    /// The last `count` lines of one stream
+    pub fn tail(&self, stream: LogStream, count: usize) -> Vec<String> {
+        let output = strip_ansi(&self.output(stream).1);
+        let lines: Vec<&str> = output.lines().collect();
+        lines[lines.len().saturating_sub(count)..]
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    /// Send a signal to the job's process. On unix `cargo run` (and rustup, with a
+    /// toolchain) replaces itself with the program it runs, so once the program has started
+    /// this reaches the program; while cargo is still building it, it reaches cargo.
+    #[cfg(unix)]
+    pub fn signal(&mut self, signal: i32) -> Result<()> {
+        if !self.is_running() {
+            return Err(anyhow!("Job {} has already exited", self.id));
+        }
+        let pid = i32::try_from(self.pid)?;
+        // Safety: kill has no memory safety requirements
+        if unsafe { libc::kill(pid, signal) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn signal(&mut self, _signal: i32) -> Result<()> {
+        Err(anyhow!("Sending signals is only supported on unix"))
+    }
+
+    /// Kill the job's process group and wait briefly for it to exit
+    pub fn kill(&mut self) {
+        self.limits.cancelled.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + KILL_WAIT;
+        while self.handle.as_ref().is_some_and(|h| !h.is_finished()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        self.poll();
+    }
+}
+
+/// Background jobs started in this session. Jobs that are still running when the server
+/// exits are killed.
+#[derive(Debug, Default)]
+pub struct Jobs {
+    jobs: BTreeMap<u64, Job>,
+    next_id: u64,
+}
+
+impl Jobs {
+    /// Run a spawned process to completion on its own thread. If its output is being
+    /// written to the command log under `record`, the record is completed when it
+    /// finishes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        &mut self,
+        process: Process,
+        limits: Limits,
+        command: JobCommand,
+        command_log: CommandLog,
+        session_id: &'static str,
+        record: Option<RunRecord>,
+        render: Render,
+    ) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+
+        let (stdout, stderr) = process.buffers();
+        let pid = process.id();
+        let started = Instant::now();
+        let run_id = record.as_ref().map(|record| record.id);
+        let thread_limits = limits.clone();
+        let (thread_stdout, thread_stderr) = (Arc::clone(&stdout), Arc::clone(&stderr));
+        let handle = thread::spawn(move || {
+            let mut output = process.wait(&thread_limits)?;
+            let duration = started.elapsed();
+            if let Some(record) = record {
+                let line_counts = (
+                    thread_stdout.lock().unwrap().line_count(),
+                    thread_stderr.lock().unwrap().line_count(),
+                );
+                finish_log(
+                    &command_log,
+                    session_id,
+                    record,
+                    line_counts,
+                    duration,
+                    &mut output,
+                );
+            }
+            Ok((output, duration))
+        });
+
+        self.jobs.insert(
+            id,
+            Job {
+                id,
+                command,
+                pid,
+                started,
+                stdout,
+                stderr,
+                run_id,
+                limits,
+                handle: Some(handle),
+                outcome: None,
+                duration: None,
+                render: Some(render),
+                rendered: None,
+            },
+        );
+
+        id
+    }
+
+    /// All jobs, oldest first
+    pub fn list(&mut self) -> impl Iterator<Item = &Job> {
+        self.jobs.values_mut().for_each(Job::poll);
+        self.jobs.values()
+    }
+
+    /// Id of the running instance with this name, if any
+    pub fn running_instance(&mut self, name: &str) -> Option<u64> {
+        self.list()
+            .find(|job| job.is_running() && job.instance() == Some(name))
+            .map(Job::id)
+    }
+
+    /// The most recent instance with this name. Without a name, the only running instance.
+    pub fn instance_mut(&mut self, name: Option<&str>) -> Result<&mut Job> {
+        let id = match name {
+            Some(name) => self
+                .list()
+                .filter(|job| job.instance() == Some(name))
+                .last()
+                .map(Job::id)
+                .ok_or_else(|| anyhow!("No instance named {name:?}"))?,
+            None => {
+                let running: Vec<_> = self
+                    .list()
+                    .filter(|job| job.is_running())
+                    .filter_map(|job| Some((job.id(), job.instance()?.to_string())))
+                    .collect();
+                match running.as_slice() {
+                    [(id, _)] => *id,
+                    [] => return Err(anyhow!("No detached instances are running")),
+                    _ => {
+                        let names: Vec<_> = running.into_iter().map(|(_, name)| name).collect();
+                        return Err(anyhow!(
+                            "Several instances are running ({}); pass a name",
+                            names.join(", ")
+                        ));
+                    }
+                }
+            }
+        };
+        self.get_mut(id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Result<&mut Job> {
+        let job = self
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("No job with id {id}. Use job_status to list jobs."))?;
+        job.poll();
+        Ok(job)
+    }
+}
+
+impl Drop for Jobs {
+    fn drop(&mut self) {
+        for job in self.jobs.values() {
+            job.limits.cancelled.store(true, Ordering::SeqCst);
+        }
+        for job in self.jobs.values_mut() {
+            if job.handle.as_ref().is_some_and(|h| !h.is_finished()) {
+                log::info!("killing job {} ({})", job.id, job.command.command_line);
+            }
+            job.join();
+        }
+    }
+}
+
+/// The result a finished job's tool would have returned, rendered on first use. None if
+/// the job is still running.
+pub fn job_result(state: &mut CargoTools, id: u64) -> Result<Option<String>> {
+    let job = state.jobs_mut().get_mut(id)?;
+    if let Some(rendered) = &job.rendered {
+        return Ok(Some(rendered.clone()));
+    }
+
+    let output = match &job.outcome {
+        None => return Ok(None),
+        Some(Err(e)) => return Ok(Some(format!("❌ Job could not be run: {e}\n"))),
+        Some(Ok(output)) => Arc::clone(output),
+    };
+    let Some(render) = job.render.take() else {
+        return Ok(None);
+    };
+
+    let rendered = render(&output, state)
+        .unwrap_or_else(|e| format!("❌ Could not report the job's result: {e}\n"));
+    state.jobs_mut().get_mut(id)?.rendered = Some(rendered.clone());
+    Ok(Some(rendered))
+}
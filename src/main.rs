@@ -1,3 +1,6 @@
+mod command_log;
+mod jobs;
+mod server;
 mod state;
 mod tools;
 
@@ -15,5 +18,9 @@ Use set_working_directory to set the project directory first, then run cargo com
 fn main() -> Result<()> {
     let mut state = CargoTools::new()?;
 
-    mcplease::run::<tools::Tools, _>(&mut state, server_info!(), Some(INSTRUCTIONS))
+    if std::env::args().nth(1).as_deref() == Some("serve") {
+        server::serve(&mut state, server_info!(), Some(INSTRUCTIONS))
+    } else {
+        mcplease::run::<tools::Tools, _>(&mut state, server_info!(), Some(INSTRUCTIONS))
+    }
 }
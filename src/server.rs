@@ -0,0 +1,266 @@
+use crate::{state::CargoTools, tools::Tools};
+use anyhow::Result;
+use env_logger::{Builder, Target};
+use mcplease::types::{Info, McpMessage, McpNotification, McpRequest};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+/// Per-call context available to tools while they execute
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    cancelled: Arc<AtomicBool>,
+    progress: Option<ProgressReporter>,
+}
+
+impl RequestContext {
+    /// Flag that is set when the client cancels this call
+    pub fn cancelled(&self) -> &Arc<AtomicBool> {
+        &self.cancelled
+    }
+
+    /// Where to report progress, if the client asked for progress notifications
+    pub fn progress(&self) -> Option<&ProgressReporter> {
+        self.progress.as_ref()
+    }
+
+    /// This context without progress reporting, for tools that run several commands and
+    /// report their own progress across all of them
+    pub fn without_progress(&self) -> Self {
+        Self {
+            cancelled: Arc::clone(&self.cancelled),
+            progress: None,
+        }
+    }
+}
+
+/// Sends `notifications/progress` for a tool call whose request carried a progress token
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    token: Value,
+}
+
+impl ProgressReporter {
+    /// Report progress. `progress` must increase with every notification; `total` is
+    /// omitted while unknown.
+    pub fn notify(&self, progress: u64, total: Option<u64>, message: &str) {
+        let mut params = json!({
+            "progressToken": self.token,
+            "progress": progress,
+            "message": message,
+        });
+        if let Some(total) = total {
+            params["total"] = total.into();
+        }
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": params,
+        });
+
+        log::trace!("-> {notification}");
+        let mut stdout = std::io::stdout().lock();
+        let written = writeln!(stdout, "{notification}").and_then(|()| stdout.flush());
+        if let Err(e) = written {
+            log::error!("Could not send progress notification: {e}");
+        }
+    }
+}
+
+/// Cancellation flags of tool calls that have been received but have not finished, by
+/// request id
+type Pending = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+
+/// Serve MCP over stdio.
+///
+/// This is mcplease's serve loop, except that stdin is read on its own thread so that
+/// `notifications/cancelled` can reach a tool call while it is still running, and that
+/// tool calls can send progress notifications.
+pub fn serve(
+    state: &mut CargoTools,
+    server_info: Info,
+    instructions: Option<&'static str>,
+) -> Result<()> {
+    init_logging();
+    log::trace!("started!");
+    #[cfg(unix)]
+    stop_commands_on_exit_signals();
+
+    let pending = Pending::default();
+    let (sender, receiver) = mpsc::channel();
+    let reader_pending = pending.clone();
+    thread::spawn(move || read_messages(sender, reader_pending));
+
+    let mut stdout = std::io::stdout();
+    for (request, cancelled) in receiver {
+        let key = request.id.to_string();
+
+        if !cancelled.load(Ordering::SeqCst) {
+            let progress = request
+                .params
+                .as_ref()
+                .and_then(|params| params.pointer("/_meta/progressToken"))
+                .map(|token| ProgressReporter {
+                    token: token.clone(),
+                });
+            state.set_request(RequestContext {
+                cancelled: Arc::clone(&cancelled),
+                progress,
+            });
+            let response = request.execute::<CargoTools, Tools>(state, instructions, &server_info);
+            state.set_request(RequestContext::default());
+
+            // Cancelled calls get no response
+            if !cancelled.load(Ordering::SeqCst) {
+                let response_str = serde_json::to_string(&response)?;
+                log::trace!("-> {response_str}");
+                stdout.write_all(response_str.as_bytes())?;
+                stdout.write_all(b"\n")?;
+                stdout.flush()?;
+            }
+        }
+
+        if cancelled.load(Ordering::SeqCst) {
+            log::info!("request {key} was cancelled");
+        }
+        pending.lock().unwrap().remove(&key);
+    }
+
+    Ok(())
+}
+
+/// Read messages from stdin, passing requests on to the serve loop and handling
+/// cancellation notifications immediately
+fn read_messages(sender: mpsc::Sender<(McpRequest, Arc<AtomicBool>)>, pending: Pending) {
+    let mut reader = BufReader::new(std::io::stdin());
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => break, // EOF
+            Ok(_) => {
+                log::trace!("<- {line}");
+                match serde_json::from_str(&line) {
+                    Ok(McpMessage::Request(request)) => {
+                        let cancelled = Arc::new(AtomicBool::new(false));
+                        pending
+                            .lock()
+                            .unwrap()
+                            .insert(request.id.to_string(), Arc::clone(&cancelled));
+                        if sender.send((request, cancelled)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(McpMessage::Notification(notification)) => {
+                        handle_notification(notification, &pending);
+                    }
+                    Err(e) => {
+                        log::error!("{e:?}");
+                    }
+                }
+            }
+            Err(e) => {
+                log::error!("Error reading line: {e}");
+                break;
+            }
+        }
+    }
+}
+
+fn handle_notification(notification: McpNotification, pending: &Pending) {
+    if notification.method != "notifications/cancelled" {
+        log::trace!("received {notification:?}, ignoring");
+        return;
+    }
+
+    let Some(request_id) = notification
+        .params
+        .as_ref()
+        .and_then(|params| params.get("requestId"))
+        .map(Value::to_string)
+    else {
+        log::error!("cancellation without a requestId: {notification:?}");
+        return;
+    };
+
+    match pending.lock().unwrap().get(&request_id) {
+        Some(cancelled) => {
+            log::info!("cancelling request {request_id}");
+            cancelled.store(true, Ordering::SeqCst);
+        }
+        None => log::debug!("request {request_id} is not in flight, ignoring cancellation"),
+    }
+}
+
+/// When the server is told to exit with SIGTERM, SIGINT or SIGHUP, stop the commands and
+/// background jobs that are still running before exiting, rather than leaving them behind.
+/// The signal handler only writes the signal to a socket; a thread of its own does the rest.
+#[cfg(unix)]
+fn stop_commands_on_exit_signals() {
+    use std::{
+        io::Read,
+        os::{fd::IntoRawFd, unix::net::UnixStream},
+        sync::atomic::AtomicI32,
+    };
+
+    static SIGNALS: AtomicI32 = AtomicI32::new(-1);
+
+    extern "C" fn on_signal(signal: libc::c_int) {
+        let byte = signal as u8;
+        // Safety: write is async-signal-safe, and the descriptor is never closed
+        unsafe { libc::write(SIGNALS.load(Ordering::SeqCst), (&raw const byte).cast(), 1) };
+    }
+
+    let (mut receiver, sender) = match UnixStream::pair() {
+        Ok(pair) => pair,
+        Err(e) => {
+            log::error!("Could not set up signal handling: {e}");
+            return;
+        }
+    };
+    SIGNALS.store(sender.into_raw_fd(), Ordering::SeqCst);
+
+    thread::spawn(move || {
+        let mut signal = [0];
+        if receiver.read_exact(&mut signal).is_ok() {
+            log::info!("received signal {}, stopping running commands", signal[0]);
+            crate::tools::process::terminate_all();
+            std::process::exit(128 + i32::from(signal[0]));
+        }
+    });
+
+    for signal in [libc::SIGTERM, libc::SIGINT, libc::SIGHUP] {
+        // Safety: the handler only does async-signal-safe work
+        unsafe { libc::signal(signal, on_signal as *const () as libc::sighandler_t) };
+    }
+}
+
+/// Log to the file named by MCP_LOG_LOCATION, as mcplease does
+fn init_logging() {
+    let Ok(log_location) = std::env::var("MCP_LOG_LOCATION") else {
+        return;
+    };
+
+    let path = PathBuf::from(&*shellexpand::tilde(&log_location));
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Builder::from_default_env()
+            .target(Target::Pipe(Box::new(file)))
+            .init(),
+        Err(e) => eprintln!("Could not open log file {}: {e}", path.display()),
+    }
+}
@@ -1,3 +1,9 @@
+use crate::command_log::CommandLog;
+use crate::jobs::Jobs;
+use crate::server::RequestContext;
+use crate::tools::{
+    cargo_utils::DEFAULT_TIMEOUT_SECS, diagnostics::Diagnostic, output::DEFAULT_OUTPUT_BUDGET,
+};
 use anyhow::{Result, anyhow};
 use fieldwork::Fieldwork;
 use mcplease::session::SessionStore;
@@ -19,6 +25,25 @@ pub struct SharedContextData {
 pub struct CargoSessionData {
     /// Default toolchain to use for cargo commands (e.g., "stable", "nightly", "1.70.0")
     default_toolchain: Option<String>,
+    /// Maximum bytes of command output to return, 0 for unlimited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_budget: Option<usize>,
+    /// Seconds a cargo command may run before it is killed, 0 for no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_secs: Option<u64>,
+    /// Diagnostics from the most recent cargo_check or cargo_clippy run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_diagnostics: Option<CapturedDiagnostics>,
+}
+
+/// Diagnostics captured from a cargo run, kept so that their suggestions can be applied later
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CapturedDiagnostics {
+    /// The cargo command that produced these diagnostics (e.g. "cargo clippy")
+    pub command: String,
+    /// Project directory the command ran in
+    pub project_path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 /// Cargo tools with session support
@@ -29,6 +54,13 @@ pub struct CargoTools {
     session_store: SessionStore<CargoSessionData>,
     /// Shared context store for cross-server communication (working directory)
     shared_context_store: SessionStore<SharedContextData>,
+    /// Full output of every cargo command run, for cargo_log
+    command_log: CommandLog,
+    /// Commands running in the background
+    jobs: Jobs,
+    /// Context of the tool call currently being executed
+    #[field(set)]
+    request: RequestContext,
     #[field(set, with)]
     default_session_id: &'static str,
 }
@@ -38,6 +70,9 @@ impl Debug for CargoTools {
         f.debug_struct("CargoTools")
             .field("session_store", &self.session_store)
             .field("shared_context_store", &self.shared_context_store)
+            .field("command_log", &self.command_log)
+            .field("jobs", &self.jobs)
+            .field("request", &self.request)
             .field("default_session_id", &self.default_session_id)
             .finish()
     }
@@ -60,9 +95,19 @@ impl CargoTools {
         shared_path.push("shared-context.json");
         let shared_context_store = SessionStore::new(Some(shared_path))?;
 
+        // Command output logs, one directory per session
+        let mut log_path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        log_path.push(".ai-tools");
+        log_path.push("logs");
+        log_path.push("cargo-mcp");
+        let command_log = CommandLog::new(log_path);
+
         let mut tools = Self {
             session_store,
             shared_context_store,
+            command_log,
+            jobs: Jobs::default(),
+            request: RequestContext::default(),
             default_session_id: "default",
         };
 
@@ -74,6 +119,28 @@ impl CargoTools {
             tools.set_default_toolchain(Some(toolchain), None)?;
         }
 
+        // Check for an output budget from environment variable
+        if let Ok(budget) = std::env::var("CARGO_MCP_OUTPUT_BUDGET")
+            && !budget.is_empty()
+        {
+            let budget = budget
+                .parse()
+                .map_err(|e| anyhow!("Invalid CARGO_MCP_OUTPUT_BUDGET {budget:?}: {e}"))?;
+            log::info!("Setting output budget from CARGO_MCP_OUTPUT_BUDGET: {budget}");
+            tools.set_output_budget(Some(budget), None)?;
+        }
+
+        // Check for a command timeout from environment variable
+        if let Ok(timeout) = std::env::var("CARGO_MCP_TIMEOUT_SECS")
+            && !timeout.is_empty()
+        {
+            let timeout = timeout
+                .parse()
+                .map_err(|e| anyhow!("Invalid CARGO_MCP_TIMEOUT_SECS {timeout:?}: {e}"))?;
+            log::info!("Setting command timeout from CARGO_MCP_TIMEOUT_SECS: {timeout}");
+            tools.set_timeout_secs(Some(timeout), None)?;
+        }
+
         Ok(tools)
     }
 
@@ -124,6 +191,60 @@ impl CargoTools {
         })
     }
 
+    /// Get the output budget for this session, in bytes (0 means unlimited)
+    pub fn get_output_budget(&mut self, session_id: Option<&str>) -> Result<usize> {
+        let session_data = self.get_cargo_session(session_id)?;
+        Ok(session_data.output_budget.unwrap_or(DEFAULT_OUTPUT_BUDGET))
+    }
+
+    /// Set the output budget for this session
+    pub fn set_output_budget(
+        &mut self,
+        budget: Option<usize>,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.update_cargo_session(session_id, |data| {
+            data.output_budget = budget;
+        })
+    }
+
+    /// Get the command timeout for this session, in seconds (0 means no limit)
+    pub fn get_timeout_secs(&mut self, session_id: Option<&str>) -> Result<u64> {
+        let session_data = self.get_cargo_session(session_id)?;
+        Ok(session_data.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+
+    /// Set the command timeout for this session
+    pub fn set_timeout_secs(
+        &mut self,
+        timeout_secs: Option<u64>,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.update_cargo_session(session_id, |data| {
+            data.timeout_secs = timeout_secs;
+        })
+    }
+
+    /// Get the diagnostics captured from the most recent check or clippy run
+    pub fn get_last_diagnostics(
+        &mut self,
+        session_id: Option<&str>,
+    ) -> Result<Option<CapturedDiagnostics>> {
+        let session_data = self.get_cargo_session(session_id)?;
+        Ok(session_data.last_diagnostics.clone())
+    }
+
+    /// Replace the captured diagnostics for this session
+    pub fn set_last_diagnostics(
+        &mut self,
+        diagnostics: Option<CapturedDiagnostics>,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.update_cargo_session(session_id, |data| {
+            data.last_diagnostics = diagnostics;
+        })
+    }
+
     /// Check if the current working directory is a Rust project
     pub fn ensure_rust_project(&mut self, session_id: Option<&str>) -> Result<PathBuf> {
         let context = self
@@ -5,3 +5,918 @@ use mcplease::traits::AsToolsList;
 fn tools_doesnt_panic() {
     Tools::tools_list();
 }
+
+#[test]
+fn parses_compiler_messages() {
+    use crate::tools::diagnostics::parse_diagnostics;
+
+    let stdout = r#"{"reason":"compiler-message","package_id":"demo","message":{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":null},"level":"error","spans":[{"file_name":"src/main.rs","byte_start":29,"byte_end":32,"line_start":2,"line_end":2,"column_start":18,"column_end":21,"is_primary":true,"label":"expected `u32`, found `&str`","suggested_replacement":null,"suggestion_applicability":null,"expansion":null,"text":[]},{"file_name":"src/main.rs","byte_start":23,"byte_end":26,"line_start":2,"line_end":2,"column_start":12,"column_end":15,"is_primary":false,"label":"expected due to this","suggested_replacement":null,"suggestion_applicability":null,"expansion":null,"text":[]}],"children":[],"rendered":"error[E0308]: mismatched types"}}
+{"reason":"compiler-message","package_id":"demo","message":{"$message_type":"diagnostic","message":"For more information about this error, try `rustc --explain E0308`.","code":null,"level":"failure-note","spans":[],"children":[],"rendered":null}}
+{"reason":"build-finished","success":false}"#;
+
+    let diagnostics = parse_diagnostics(stdout);
+    assert_eq!(diagnostics.len(), 1);
+
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.code.as_deref(), Some("E0308"));
+    assert!(diagnostic.is_error());
+    let primary = diagnostic.primary.as_ref().unwrap();
+    assert_eq!(
+        (primary.file.as_str(), primary.line, primary.column),
+        ("src/main.rs", 2, 18)
+    );
+    assert_eq!(diagnostic.secondary.len(), 1);
+}
+
+#[test]
+fn parses_libtest_text_output() {
+    use crate::tools::test_results::{TestStatus, parse_test_output};
+
+    let stdout = "
+running 3 tests
+test tests::fails ... FAILED
+test tests::ignored ... ignored, slow
+test tests::passes ... ok
+
+failures:
+
+---- tests::fails stdout ----
+some output
+
+thread 'tests::fails' (6323) panicked at src/lib.rs:13:43:
+assertion `left == right` failed
+  left: 3
+ right: 4
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::fails
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+";
+    let stderr = "     Running unittests src/lib.rs (target/debug/deps/demo-8348ca7a80742723)\n";
+
+    let report = parse_test_output(stdout, stderr);
+    assert_eq!(report.suites, 1);
+    assert_eq!(report.count(TestStatus::Passed), 1);
+    assert_eq!(report.count(TestStatus::Ignored), 1);
+
+    let failed = &report.tests[0];
+    assert_eq!(failed.status, TestStatus::Failed);
+    assert_eq!(failed.target.as_deref(), Some("unittests src/lib.rs"));
+    assert_eq!(failed.panic_location.as_deref(), Some("src/lib.rs:13:43"));
+    assert_eq!(
+        failed.panic_message.as_deref(),
+        Some("assertion `left == right` failed\n  left: 3\n right: 4")
+    );
+    assert!(failed.output.as_deref().unwrap().starts_with("some output"));
+}
+
+#[test]
+fn truncation_keeps_head_tail_and_errors() {
+    use crate::tools::output::{denoise, truncate_to_budget};
+
+    let mut text = String::from("\x1b[1m\x1b[32m   Compiling\x1b[0m foo v0.1.0\n");
+    text.push_str("   Compiling bar v0.1.0\n");
+    for i in 0..200 {
+        text.push_str(&format!("noise line {i}\n"));
+    }
+    text.push_str("error[E0308]: mismatched types\n  --> src/main.rs:2:18\n\n");
+    for i in 200..400 {
+        text.push_str(&format!("noise line {i}\n"));
+    }
+
+    let text = denoise(&text);
+    assert!(text.starts_with("[2 progress lines collapsed: 2 Compiling]\n"));
+
+    let (truncated, truncation) = truncate_to_budget(&text, 1000);
+    let truncation = truncation.unwrap();
+    assert!(truncated.len() < 1200);
+    assert!(truncated.contains("noise line 0\n"));
+    assert!(truncated.contains("noise line 399\n"));
+    assert!(truncated.contains("error[E0308]: mismatched types\n  --> src/main.rs:2:18\n"));
+    assert!(truncated.contains("lines elided"));
+    assert!(truncation.kept_lines < truncation.total_lines);
+}
+
+#[test]
+fn command_log_records_and_prunes_runs() {
+    use crate::command_log::{CommandLog, LogStream, RunRecord};
+
+    let root = std::env::temp_dir().join(format!("cargo-mcp-log-test-{}", std::process::id()));
+    let log = CommandLog::new(root.clone());
+    let record = RunRecord {
+        id: 0,
+        command_name: "cargo build".into(),
+        command: "cargo build".into(),
+        project_path: "/tmp/project".into(),
+        exit_code: Some(0),
+        interruption: None,
+        running: false,
+        finished_at: 0,
+        duration_ms: 10,
+        stdout_lines: 0,
+        stderr_lines: 0,
+    };
+
+    for run in 0..105 {
+        log.record("test", record.clone(), "", &format!("run {run}\n"))
+            .unwrap();
+    }
+
+    let latest = log.get("test", None).unwrap();
+    assert_eq!(latest.id, 105);
+    assert_eq!(latest.stderr_lines, 1);
+    assert_eq!(
+        log.read("test", 105, LogStream::Stderr).unwrap(),
+        "run 104\n"
+    );
+    assert!(log.get("test", Some(5)).is_err());
+    assert!(log.get("test", Some(6)).is_ok());
+    assert_eq!(log.list("test", 3).len(), 3);
+
+    // A run that is still going is readable, and isn't pruned until it has finished
+    let (started, mut stdout, _) = log.start("test", record.clone()).unwrap();
+    assert_eq!(started.id, 106);
+    std::io::Write::write_all(&mut stdout, b"partial\n").unwrap();
+    assert!(log.get("test", Some(106)).unwrap().running);
+    assert_eq!(
+        log.read("test", 106, LogStream::Stdout).unwrap(),
+        "partial\n"
+    );
+    for run in 0..100 {
+        log.record("test", record.clone(), "", &format!("run {run}\n"))
+            .unwrap();
+    }
+    assert!(log.get("test", Some(106)).unwrap().running);
+    let finished = log.finish("test", started).unwrap();
+    assert!(!finished.running);
+    assert!(log.get("test", Some(106)).is_err());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_only_the_tail_of_capped_output() {
+    use crate::tools::process::{Capture, Limits, Process};
+
+    let dir = std::env::temp_dir().join(format!("cargo-mcp-capture-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files = (
+        std::fs::File::create(dir.join("stdout")).unwrap(),
+        std::fs::File::create(dir.join("stderr")).unwrap(),
+    );
+    let capture = Capture {
+        limit: Some(1000),
+        files: Some(files),
+    };
+
+    let mut cmd = std::process::Command::new("seq");
+    cmd.args(["1", "20000"]);
+    let process = Process::spawn_with(&mut cmd, &dir, None, capture).unwrap();
+    let (stdout, _) = process.buffers();
+    let output = process.wait(&Limits::default()).unwrap();
+
+    assert!(output.stdout.len() <= 2000);
+    assert!(output.stdout.ends_with("\n19999\n20000\n"));
+    let first = output.stdout.lines().next().unwrap();
+    assert_eq!(
+        stdout.lock().unwrap().dropped_lines(),
+        first.parse::<usize>().unwrap() - 1
+    );
+    assert_eq!(stdout.lock().unwrap().line_count(), 20000);
+
+    let mut read = 0;
+    let lines = stdout.lock().unwrap().lines_since(&mut read);
+    assert_eq!(lines.first().map(String::as_str), Some(first));
+    assert!(read > 2000);
+
+    let logged = std::fs::read_to_string(dir.join("stdout")).unwrap();
+    assert_eq!(logged.lines().count(), 20000);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn stops_waiting_once_the_command_exits() {
+    use crate::tools::process::{Limits, run_process};
+    use std::time::{Duration, Instant};
+
+    // The backgrounded sleep keeps stdout open after the shell has exited
+    let mut cmd = std::process::Command::new("sh");
+    cmd.args(["-c", "sleep 5 & echo started"]);
+    let limits = Limits {
+        timeout: Some(Duration::from_secs(3)),
+        ..Limits::default()
+    };
+    let started = Instant::now();
+    let output = run_process(&mut cmd, &std::env::temp_dir(), None, &limits).unwrap();
+
+    assert!(output.success());
+    assert_eq!(output.stdout, "started\n");
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn strips_cargo_progress_bar_frames() {
+    use crate::tools::progress::strip_progress_bar;
+
+    let stderr = "   Compiling demo v0.1.0 (/tmp/demo)\n    Building [      ] 0/2: demo   \r    \
+                  Building [====> ] 1/2: demo(bin)   \r    Finished `dev` profile in 0.19s\n";
+
+    assert_eq!(
+        strip_progress_bar(stderr),
+        "   Compiling demo v0.1.0 (/tmp/demo)\n    Finished `dev` profile in 0.19s\n"
+    );
+    assert_eq!(
+        strip_progress_bar("line one\r\nline two\r\n"),
+        "line one\r\nline two\r\n"
+    );
+}
+
+#[test]
+fn parses_cargo_tree_output() {
+    use crate::tools::dependency_tree::{dependency_chains, edges, parse_tree};
+
+    let stdout = "app v0.1.0 (/tmp/app)
+├── serde_json v1.0.141
+│   └── serde v1.0.219
+└── serde v1.0.219 (*)
+[build-dependencies]
+└── cc v1.2.0
+";
+    let lines = parse_tree(stdout);
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[2].depth, 2);
+    assert!(lines[3].deduplicated);
+    assert_eq!(lines[3].label, "serde v1.0.219");
+    assert_eq!(lines[4].kind.as_deref(), Some("build"));
+
+    let edges = edges(&lines, false);
+    assert_eq!(edges.len(), 4);
+    assert_eq!(
+        (
+            edges[3].from.as_str(),
+            edges[3].to.as_str(),
+            edges[3].kind.as_str()
+        ),
+        ("app v0.1.0 (/tmp/app)", "cc v1.2.0", "build")
+    );
+
+    let inverted = "serde v1.0.219
+├── app v0.1.0 (/tmp/app)
+└── serde_json v1.0.141
+    └── app v0.1.0 (/tmp/app)
+";
+    assert_eq!(
+        dependency_chains(&parse_tree(inverted)),
+        vec![
+            vec!["app v0.1.0 (/tmp/app)", "serde v1.0.219"],
+            vec![
+                "app v0.1.0 (/tmp/app)",
+                "serde_json v1.0.141",
+                "serde v1.0.219"
+            ],
+        ]
+    );
+}
+
+#[test]
+fn diffs_lockfiles_and_parses_update_output() {
+    use crate::tools::lockfile::{
+        ChangeKind, compare_versions, diff_lockfiles, parse_update_output, semver_compatible,
+    };
+    use std::cmp::Ordering;
+
+    let package = |name: &str, version: &str| {
+        format!("[[package]]\nname = \"{name}\"\nversion = \"{version}\"\n\n")
+    };
+    let before = [
+        package("serde", "1.0.1"),
+        package("syn", "1.0.109"),
+        package("old", "0.1.0"),
+    ]
+    .concat();
+    let after = [
+        package("serde", "1.0.2"),
+        package("syn", "1.0.109"),
+        package("syn", "2.0.104"),
+        package("log", "0.4.27"),
+    ]
+    .concat();
+
+    let changes: Vec<_> = diff_lockfiles(&before, &after)
+        .into_iter()
+        .map(|c| (c.change, c.name, c.from, c.to))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (ChangeKind::Added, "log".into(), None, Some("0.4.27".into())),
+            (
+                ChangeKind::Removed,
+                "old".into(),
+                Some("0.1.0".into()),
+                None
+            ),
+            (
+                ChangeKind::Upgraded,
+                "serde".into(),
+                Some("1.0.1".into()),
+                Some("1.0.2".into())
+            ),
+            (
+                ChangeKind::Added,
+                "syn".into(),
+                None,
+                Some("2.0.104".into())
+            ),
+        ]
+    );
+
+    assert!(semver_compatible("1.2.0", "1.9.3"));
+    assert!(semver_compatible("0.3.1", "0.3.4"));
+    assert!(!semver_compatible("0.3.1", "0.4.0"));
+    assert!(!semver_compatible("0.0.1", "0.0.2"));
+    assert!(!semver_compatible("1.3.2", "2.0.0"));
+
+    assert_eq!(
+        compare_versions("1.0.0-alpha.10", "1.0.0-alpha.9"),
+        Ordering::Greater
+    );
+    assert_eq!(
+        compare_versions("1.0.0-alpha", "1.0.0-alpha.1"),
+        Ordering::Less
+    );
+    assert_eq!(compare_versions("1.0.0-1", "1.0.0-alpha"), Ordering::Less);
+    assert_eq!(compare_versions("1.0.0-rc.1", "1.0.0"), Ordering::Less);
+    assert_eq!(compare_versions("1.0.0+build.5", "1.0.0"), Ordering::Equal);
+
+    let stderr = "    Updating crates.io index
+     Locking 2 packages to latest compatible versions
+    Updating anyhow v1.0.98 -> v1.0.100
+ Downgrading foo v0.2.0 -> v0.1.9
+      Adding bar v1.0.0
+warning: aborting update due to dry run
+";
+    let changes = parse_update_output(stderr);
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0].change, ChangeKind::Upgraded);
+    assert_eq!(changes[0].to.as_deref(), Some("1.0.100"));
+    assert_eq!(changes[1].change, ChangeKind::Downgraded);
+    assert_eq!(changes[2].change, ChangeKind::Added);
+}
+
+#[test]
+fn edits_manifest_keeping_formatting() {
+    use crate::tools::manifest::Manifest;
+
+    let original = "[package]\nname = \"demo\" # the name\n\n[features]\ndefault = []\n";
+    let mut manifest = Manifest {
+        original: original.into(),
+        document: original.parse().unwrap(),
+    };
+    manifest
+        .edit("features.json", Some(r#"["dep:serde_json"]"#))
+        .unwrap();
+    manifest.edit("profile.release.lto", Some("fat")).unwrap();
+    manifest
+        .edit("bin.tool", Some(r#"{ path = "src/tool.rs" }"#))
+        .unwrap();
+    manifest.edit("package.name", Some(r#""renamed""#)).unwrap();
+    assert_eq!(
+        manifest.document.to_string(),
+        "[package]\nname = \"renamed\" # the name\n\n[features]\ndefault = []\n\
+         json = [\"dep:serde_json\"]\n\n[profile.release]\nlto = \"fat\"\n\n\
+         [[bin]]\nname = \"tool\"\npath = \"src/tool.rs\"\n"
+    );
+
+    manifest.edit("bin.tool", None).unwrap();
+    assert!(!manifest.document.to_string().contains("[[bin]]"));
+    assert!(manifest.edit("dependencies.serde", Some("1")).is_err());
+    assert!(manifest.edit("package.missing", None).is_err());
+}
+
+#[test]
+fn orders_lint_flags_and_edits_lint_tables() {
+    use crate::tools::lints::{LintLevel, lint_args, lint_settings, set_lint};
+
+    let strings = |lints: &[&str]| {
+        lints
+            .iter()
+            .map(|lint| lint.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        lint_args(
+            &strings(&["clippy::too_many_lines"]),
+            &strings(&["clippy::pedantic"]),
+            &strings(&["warnings"]),
+        ),
+        strings(&[
+            "-W",
+            "clippy::pedantic",
+            "-D",
+            "warnings",
+            "-A",
+            "clippy::too_many_lines"
+        ])
+    );
+
+    let mut lints = toml_edit::Table::new();
+    set_lint(&mut lints, "clippy::pedantic", Some(LintLevel::Warn)).unwrap();
+    set_lint(&mut lints, "unsafe_code", Some(LintLevel::Forbid)).unwrap();
+    set_lint(&mut lints, "clippy::pedantic", Some(LintLevel::Deny)).unwrap();
+    let settings: Vec<_> = lint_settings("workspace", &lints)
+        .into_iter()
+        .map(|s| (s.lint, s.level, s.priority))
+        .collect();
+    assert_eq!(
+        settings,
+        vec![
+            ("clippy::pedantic".into(), "deny".into(), Some(-1)),
+            ("unsafe_code".into(), "forbid".into(), None),
+        ]
+    );
+
+    set_lint(&mut lints, "unsafe_code", None).unwrap();
+    assert!(lints.get("rust").is_none());
+    assert!(set_lint(&mut lints, "unsafe_code", None).is_err());
+}
+
+#[test]
+fn resolves_package_paths_inside_the_working_directory() {
+    use crate::tools::cargo_utils::resolve_inside;
+
+    let root = std::env::temp_dir().join(format!("cargo-mcp-resolve-test-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("crates")).unwrap();
+    let root = root.canonicalize().unwrap();
+
+    assert_eq!(
+        resolve_inside(&root, "crates/new"),
+        Some(root.join("crates/new"))
+    );
+    assert_eq!(
+        resolve_inside(&root, "./crates/../tools/new"),
+        Some(root.join("tools/new"))
+    );
+    assert_eq!(
+        resolve_inside(&root, &root.join("new").display().to_string()),
+        Some(root.join("new"))
+    );
+    assert!(resolve_inside(&root, "../new").is_none());
+    assert!(resolve_inside(&root, "crates/../../new").is_none());
+    assert!(
+        resolve_inside(
+            &root,
+            &std::env::temp_dir().join("new").display().to_string()
+        )
+        .is_none()
+    );
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(std::env::temp_dir(), root.join("outside")).unwrap();
+        assert!(resolve_inside(&root, "outside/new").is_none());
+        assert!(resolve_inside(&root, "outside/deeper/new").is_none());
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn merges_member_dependency_declarations() {
+    use crate::tools::declarations::{
+        Location, check_existing, merge, parse_declaration, plain_requirement,
+    };
+    use std::collections::BTreeSet;
+
+    let location = Location {
+        target: None,
+        kind: "dependencies",
+    };
+    let declaration = |member: &str, entry: &str| {
+        let document: toml_edit::DocumentMut = format!("serde = {entry}").parse().unwrap();
+        parse_declaration(
+            &document["serde"],
+            member,
+            0,
+            &location,
+            std::path::Path::new("/tmp"),
+        )
+        .unwrap()
+    };
+    let features = |features: &[&str]| -> BTreeSet<String> {
+        features.iter().map(|feature| feature.to_string()).collect()
+    };
+
+    assert_eq!(plain_requirement("1.2"), Some("1.2"));
+    assert_eq!(plain_requirement("^1.2.3"), Some("1.2.3"));
+    assert_eq!(plain_requirement("~1.2"), None);
+    assert_eq!(plain_requirement(">=1, <3"), None);
+    assert_eq!(plain_requirement("1.0.0-rc.1"), None);
+
+    let merged = merge(&[
+        declaration(
+            "a",
+            r#"{ version = "1.0.100", features = ["derive", "rc"] }"#,
+        ),
+        declaration("b", r#"{ version = "^1.0.200", features = ["derive"] }"#),
+        declaration("c", r#""1""#),
+    ]);
+    assert_eq!(merged, Ok((Some("^1.0.200".into()), features(&[]))));
+
+    let merged = merge(&[
+        declaration("a", r#"{ version = "1", features = ["derive", "rc"] }"#),
+        declaration("b", r#"{ version = "1", features = ["derive", "std"] }"#),
+    ]);
+    assert_eq!(merged, Ok((Some("1".into()), features(&["derive"]))));
+
+    let incompatible = merge(&[declaration("a", r#""0.3""#), declaration("b", r#""0.4""#)]);
+    assert!(incompatible.unwrap_err().starts_with("semver-incompatible"));
+
+    let not_plain = merge(&[declaration("a", r#""~1.2""#), declaration("b", r#""1.3""#)]);
+    assert!(not_plain.unwrap_err().contains("can't be combined"));
+
+    let renamed = merge(&[
+        declaration("a", r#""1""#),
+        declaration("b", r#"{ version = "1", package = "serde_derive" }"#),
+    ]);
+    assert!(renamed.is_err());
+
+    let existing = declaration(
+        "workspace",
+        r#"{ version = "1.0.100", features = ["derive"] }"#,
+    );
+    assert!(check_existing(&existing, &declaration("a", r#""1""#)).is_ok());
+    assert!(check_existing(&existing, &declaration("a", r#""1.0.50""#)).is_ok());
+    assert!(check_existing(&existing, &declaration("a", r#""1.0.150""#)).is_err());
+    assert!(check_existing(&existing, &declaration("a", r#""0.9""#)).is_err());
+    let no_defaults = declaration("a", r#"{ version = "1", default-features = false }"#);
+    assert!(check_existing(&existing, &no_defaults).is_err());
+}
+
+#[test]
+fn bumps_versions_and_requirements() {
+    use crate::tools::release::{BumpLevel, bump_version, check_new_version, update_requirement};
+
+    let bump = |version, level| bump_version(version, level).unwrap();
+    assert_eq!(bump("1.2.3", BumpLevel::Major), "2.0.0");
+    assert_eq!(bump("0.3.1", BumpLevel::Minor), "0.4.0");
+    assert_eq!(bump("1.2.3", BumpLevel::Patch), "1.2.4");
+    assert_eq!(bump("1.2.3", BumpLevel::Prerelease), "1.2.4-alpha.1");
+    assert_eq!(bump("2.0.0-rc.1", BumpLevel::Prerelease), "2.0.0-rc.2");
+    assert_eq!(bump("2.0.0-beta", BumpLevel::Prerelease), "2.0.0-beta.1");
+    assert_eq!(bump("2.0.0-rc.1", BumpLevel::Major), "2.0.0");
+    assert_eq!(bump("1.3.0-rc.1", BumpLevel::Major), "2.0.0");
+    assert!(bump_version("1.2", BumpLevel::Patch).is_err());
+
+    assert!(check_new_version("1.2.3", "1.2.4").is_ok());
+    assert!(check_new_version("1.2.3-rc.1", "1.2.3").is_ok());
+    assert!(check_new_version("1.2.3", "1.2.3").is_err());
+    assert!(check_new_version("1.0.0-alpha.9", "1.0.0-alpha.10").is_ok());
+
+    assert_eq!(update_requirement("0.1", "0.2.0").as_deref(), Some("0.2.0"));
+    assert_eq!(
+        update_requirement("=0.1.0", "0.2.0").as_deref(),
+        Some("=0.2.0")
+    );
+    assert_eq!(
+        update_requirement("^1.2", "1.3.0").as_deref(),
+        Some("^1.3.0")
+    );
+    assert_eq!(update_requirement(">=0.1, <0.3", "0.2.0"), None);
+}
+
+#[test]
+fn selects_checks_and_rebases_suggestion_edits() {
+    use crate::tools::CargoApplySuggestions;
+    use crate::tools::diagnostics::{Diagnostic, Span, Suggestion, SuggestionEdit};
+    use crate::tools::suggestions::{EditedFile, resolve};
+    use std::path::Path;
+
+    let original = "fn main() {\n    let x = &1;\n    foo(&x);\n    bar(&x);\n}\n";
+    let edit = |needle: &str, replacement: &str| {
+        let byte_start = original.find(needle).unwrap();
+        let before = &original[..byte_start];
+        SuggestionEdit {
+            file: "src/main.rs".into(),
+            line: before.matches('\n').count() + 1,
+            column: byte_start - before.rfind('\n').map_or(0, |i| i + 1) + 1,
+            byte_start,
+            byte_end: byte_start + needle.len(),
+            replacement: replacement.into(),
+        }
+    };
+    let diagnostic =
+        |id: usize, lint: &str, applicability: &str, edit: SuggestionEdit| Diagnostic {
+            id,
+            level: "warning".into(),
+            code: Some(lint.into()),
+            message: "this could be simpler".into(),
+            primary: Some(Span {
+                file: edit.file.clone(),
+                line: edit.line,
+                column: edit.column,
+                line_end: edit.line,
+                column_end: edit.column,
+                label: None,
+            }),
+            secondary: vec![],
+            children: vec![],
+            suggestions: vec![Suggestion {
+                message: "change this".into(),
+                applicability: applicability.into(),
+                edits: vec![edit],
+            }],
+        };
+    let borrow = diagnostic(
+        1,
+        "clippy::needless_borrow",
+        "MachineApplicable",
+        edit("&x)", "x)"),
+    );
+    let unused = diagnostic(2, "unused_variables", "MaybeIncorrect", edit("x =", "_x ="));
+
+    // Selectors must all match, and MaybeIncorrect suggestions are opt-in
+    let by_id = CargoApplySuggestions {
+        ids: Some(vec![1]),
+        ..CargoApplySuggestions::default()
+    };
+    assert!(by_id.selects(&borrow) && !by_id.selects(&unused));
+    let by_lint_and_file = CargoApplySuggestions {
+        lints: Some(vec!["clippy::needless_borrow".into()]),
+        files: Some(vec!["src/lib.rs".into()]),
+        ..CargoApplySuggestions::default()
+    };
+    assert!(!by_lint_and_file.selects(&borrow));
+    assert!(!by_id.eligible(&unused.suggestions[0]));
+    let maybe_incorrect = CargoApplySuggestions {
+        include_maybe_incorrect: Some(true),
+        ..CargoApplySuggestions::default()
+    };
+    assert!(maybe_incorrect.eligible(&unused.suggestions[0]));
+
+    let mut file = EditedFile::new("src/main.rs".into(), original.into());
+    let first = borrow.suggestions[0].edits[0].clone();
+    assert_eq!(file.check(&first), Ok(()));
+    let moved = SuggestionEdit {
+        line: first.line + 1,
+        ..first.clone()
+    };
+    assert!(file.check(&moved).unwrap_err().contains("has changed"));
+    file.edits.push(first.clone());
+
+    assert!(file.is_duplicate(&first));
+    let overlapping = edit("x);", "y);");
+    assert_eq!(
+        file.check(&overlapping).unwrap_err(),
+        "overlaps with another selected suggestion"
+    );
+
+    let edited = file.edited();
+    assert_eq!(
+        edited,
+        "fn main() {\n    let x = &1;\n    foo(x);\n    bar(&x);\n}\n"
+    );
+
+    // Edits before the applied one keep their offsets, edits after it move back by the
+    // byte that was removed, and edits of the rewritten text are dropped
+    let earlier = edit("&1", "1");
+    assert_eq!(file.rebase(&earlier, &edited), Some(earlier.clone()));
+    let later = file.rebase(&edit("&x);\n}", "x);\n}"), &edited).unwrap();
+    assert_eq!(&edited[later.byte_start..later.byte_end], "&x);\n}");
+    assert_eq!((later.line, later.column), (4, 9));
+    assert_eq!(file.rebase(&overlapping, &edited), None);
+
+    let root = Path::new("/workspace");
+    assert_eq!(resolve(root, "src/main.rs"), Ok(root.join("src/main.rs")));
+    assert!(resolve(root, "../elsewhere/src/main.rs").is_err());
+    assert!(resolve(root, "/etc/passwd").is_err());
+}
+
+#[test]
+fn detects_readiness_by_pattern_or_port() {
+    use crate::tools::readiness::{ProgramOutput, Readiness};
+    use std::net::TcpListener;
+
+    let lines = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // cargo's own messages on stderr are skipped until it starts the program
+    let mut program = ProgramOutput::default();
+    let followed = program.follow(
+        lines(&["hello"]),
+        lines(&[
+            "   Compiling demo v0.1.0",
+            "\x1b[1m\x1b[32m     Running\x1b[0m `target/debug/demo`",
+        ]),
+    );
+    assert_eq!(followed, lines(&["hello"]));
+    assert!(program.started);
+    let followed = program.follow(vec![], lines(&["\x1b[33mlistening on :8080\x1b[0m"]));
+    assert_eq!(followed, lines(&["listening on :8080"]));
+
+    let pattern = Readiness::new(Some("(?i)listening on"), None).unwrap();
+    assert_eq!(pattern.check(lines(&["starting up"])), None);
+    assert_eq!(
+        pattern.check(followed).as_deref(),
+        Some("output matched: listening on :8080")
+    );
+    assert!(Readiness::new(Some("("), None).is_err());
+    assert!(Readiness::new(Some("ready"), Some(8080)).is_err());
+    assert_eq!(
+        Readiness::new(None, None).unwrap().check(vec![]).as_deref(),
+        Some("started")
+    );
+
+    // A port that something already listens on can't tell the instance is ready
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    assert!(Readiness::new(None, Some(port)).is_err());
+    drop(listener);
+    let readiness = Readiness::new(None, Some(port)).unwrap();
+    assert_eq!(readiness.check(vec![]), None);
+    let _listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    assert_eq!(
+        readiness.check(vec![]),
+        Some(format!("localhost:{port} is accepting connections"))
+    );
+}
+
+#[test]
+fn generates_feature_combinations_up_to_the_cap() {
+    use crate::tools::feature_combinations::{Subsets, binomial, combinations};
+
+    let items = ["a", "b", "c", "d"];
+    let pairs: Vec<_> = Subsets::new(&items, 2)
+        .map(|subset| subset.join(","))
+        .collect();
+    assert_eq!(pairs, ["a,b", "a,c", "a,d", "b,c", "b,d", "c,d"]);
+    assert_eq!(Subsets::new(&items, 4).count(), 1);
+    assert_eq!(Subsets::new(&items, 5).count(), 0);
+
+    assert_eq!(binomial(4, 2), 6);
+    assert_eq!(binomial(60, 30), 118_264_581_564_861_424);
+    assert_eq!(binomial(200, 100), usize::MAX);
+
+    let labels = |features: Vec<&str>, excluded: &[String], depth, max| {
+        let (combinations, skipped) = combinations(features, excluded, depth, max);
+        let labels: Vec<_> = combinations.into_iter().map(|c| c.label).collect();
+        (labels, skipped)
+    };
+    assert_eq!(
+        labels(vec!["default", "a", "b", "c"], &[], 2, 32),
+        (
+            vec![
+                "default".to_string(),
+                "no default features".into(),
+                "all features".into(),
+                "a".into(),
+                "b".into(),
+                "c".into(),
+                "a,b".into(),
+                "a,c".into(),
+                "b,c".into(),
+            ],
+            0
+        )
+    );
+    // The full set is never repeated as a subset, and what doesn't fit is counted
+    assert_eq!(labels(vec!["a", "b"], &[], 5, 32).0.len(), 5);
+    assert_eq!(
+        labels(vec!["a", "b", "c"], &[], 2, 5),
+        (
+            vec![
+                "default".to_string(),
+                "no default features".into(),
+                "all features".into(),
+                "a".into(),
+                "b".into(),
+            ],
+            4
+        )
+    );
+
+    let (excluding, skipped) = combinations(
+        vec!["default", "a", "b"],
+        &["default".to_string(), "b".to_string()],
+        1,
+        32,
+    );
+    assert_eq!(skipped, 0);
+    let all = &excluding[1];
+    assert_eq!(all.label, "all features except excluded");
+    assert_eq!(all.args, ["--no-default-features", "--features", "a"]);
+
+    // Huge feature sets are capped without generating every subset
+    let many: Vec<String> = (0..64).map(|i| format!("f{i}")).collect();
+    let (capped, skipped) = combinations(many.iter().map(String::as_str).collect(), &[], 32, 10);
+    assert_eq!(capped.len(), 10);
+    assert_eq!(skipped, usize::MAX);
+}
+
+#[test]
+fn matches_rust_versions_to_installed_toolchains() {
+    use crate::tools::cargo_utils::matching_toolchain;
+
+    let installed: Vec<String> = [
+        "stable-x86_64-unknown-linux-gnu",
+        "1.70.0-x86_64-unknown-linux-gnu",
+        "1.70.10-x86_64-unknown-linux-gnu",
+        "1.70.9-x86_64-unknown-linux-gnu",
+        "1.7.0-x86_64-unknown-linux-gnu",
+        "1.75.0-x86_64-unknown-linux-gnu",
+        "nightly-2024-01-01-x86_64-unknown-linux-gnu",
+    ]
+    .map(String::from)
+    .into();
+
+    assert_eq!(
+        matching_toolchain(&installed, "1.70"),
+        Some("1.70.10-x86_64-unknown-linux-gnu")
+    );
+    assert_eq!(
+        matching_toolchain(&installed, "1.70.0"),
+        Some("1.70.0-x86_64-unknown-linux-gnu")
+    );
+    // 1.7 is not a prefix of 1.70 or 1.75
+    assert_eq!(
+        matching_toolchain(&installed, "1.7"),
+        Some("1.7.0-x86_64-unknown-linux-gnu")
+    );
+    assert_eq!(matching_toolchain(&installed, "1.70.1"), None);
+    assert_eq!(matching_toolchain(&installed, "1.74"), None);
+}
+
+#[test]
+fn adds_workspace_dependency_entries() {
+    use crate::tools::CargoAdd;
+    use crate::tools::manifest::Manifest;
+
+    let root = std::env::temp_dir().join(format!("cargo-mcp-add-test-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("app")).unwrap();
+    std::fs::create_dir_all(root.join("util")).unwrap();
+    let root = root.canonicalize().unwrap();
+    let app = root.join("app");
+
+    let original =
+        "[workspace]\nmembers = [\"app\", \"util\"]\n\n[workspace.dependencies]\nserde = \"1\"\n";
+    let mut manifest = Manifest {
+        original: original.into(),
+        document: original.parse().unwrap(),
+    };
+
+    // Existing entries are left alone, and only versionless registry dependencies are
+    // looked up
+    let mut looked_up = vec![];
+    let registry = CargoAdd {
+        dependencies: vec!["serde".into(), "anyhow".into(), "syn@2.0".into()],
+        ..CargoAdd::default()
+    };
+    let (added, existing) = registry
+        .add_workspace_entries(&mut manifest, &root, &app, |name| {
+            looked_up.push(name.to_string());
+            Ok("1.0.98".into())
+        })
+        .unwrap();
+    assert_eq!((added, existing), (vec!["anyhow", "syn"], vec!["serde"]));
+    assert_eq!(looked_up, ["anyhow"]);
+
+    // Paths are made relative to the workspace root
+    let path = CargoAdd {
+        dependencies: vec!["util".into()],
+        path: Some("../util".into()),
+        no_default_features: Some(true),
+        ..CargoAdd::default()
+    };
+    path.add_workspace_entries(&mut manifest, &root, &app, |_| unreachable!())
+        .unwrap();
+
+    let git = CargoAdd {
+        dependencies: vec!["tool".into()],
+        git: Some("https://example.com/tool.git".into()),
+        tag: Some("v1".into()),
+        ..CargoAdd::default()
+    };
+    git.add_workspace_entries(&mut manifest, &root, &app, |_| unreachable!())
+        .unwrap();
+
+    assert_eq!(
+        manifest.document.to_string(),
+        "[workspace]\nmembers = [\"app\", \"util\"]\n\n[workspace.dependencies]\nserde = \"1\"\n\
+         anyhow = \"1.0.98\"\nsyn = \"2.0\"\n\
+         util = { path = \"util\", default-features = false }\n\
+         tool = { git = \"https://example.com/tool.git\", tag = \"v1\" }\n"
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+}
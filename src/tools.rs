@@ -1,21 +1,76 @@
 use crate::state::CargoTools;
-mod cargo_utils;
+pub(crate) mod cargo_utils;
+pub(crate) mod compile_options;
+pub(crate) mod declarations;
+pub(crate) mod dependency_tree;
+pub(crate) mod diagnostics;
+pub(crate) mod feature_combinations;
+pub(crate) mod lints;
+pub(crate) mod lockfile;
+pub(crate) mod manifest;
+pub(crate) mod metadata;
+pub(crate) mod output;
+pub(crate) mod process;
+pub(crate) mod progress;
+pub(crate) mod readiness;
+pub(crate) mod release;
+pub(crate) mod suggestions;
+pub(crate) mod test_results;
 mcplease::tools!(
     CargoTools,
     (CargoCheck, cargo_check, "cargo_check"),
     (CargoClippy, cargo_clippy, "cargo_clippy"),
+    (CargoLints, cargo_lints, "cargo_lints"),
+    (
+        CargoFeatureMatrix,
+        cargo_feature_matrix,
+        "cargo_feature_matrix"
+    ),
+    (CargoMsrvCheck, cargo_msrv_check, "cargo_msrv_check"),
+    (CargoMetadata, cargo_metadata, "cargo_metadata"),
+    (CargoTree, cargo_tree, "cargo_tree"),
+    (
+        CargoDependencyGraph,
+        cargo_dependency_graph,
+        "cargo_dependency_graph"
+    ),
+    (
+        CargoApplySuggestions,
+        cargo_apply_suggestions,
+        "cargo_apply_suggestions"
+    ),
     (CargoTest, cargo_test, "cargo_test"),
+    (CargoLog, cargo_log, "cargo_log"),
+    (JobStatus, job_status, "job_status"),
+    (JobOutput, job_output, "job_output"),
+    (JobKill, job_kill, "job_kill"),
     (CargoFmtCheck, cargo_fmt_check, "cargo_fmt_check"),
     (CargoBuild, cargo_build, "cargo_build"),
     (CargoBench, cargo_bench, "cargo_bench"),
     (CargoAdd, cargo_add, "cargo_add"),
     (CargoRemove, cargo_remove, "cargo_remove"),
     (CargoUpdate, cargo_update, "cargo_update"),
+    (CargoLockDiff, cargo_lock_diff, "cargo_lock_diff"),
+    (
+        CargoManifestEdit,
+        cargo_manifest_edit,
+        "cargo_manifest_edit"
+    ),
+    (
+        CargoWorkspaceDependencies,
+        cargo_workspace_dependencies,
+        "cargo_workspace_dependencies"
+    ),
+    (CargoNew, cargo_new, "cargo_new"),
+    (CargoVersionBump, cargo_version_bump, "cargo_version_bump"),
     (CargoClean, cargo_clean, "cargo_clean"),
     (
         SetWorkingDirectory,
         set_working_directory,
         "set_working_directory"
     ),
-    (CargoRun, cargo_run, "cargo_run")
+    (CargoRun, cargo_run, "cargo_run"),
+    (CargoRunLogs, cargo_run_logs, "cargo_run_logs"),
+    (CargoRunSignal, cargo_run_signal, "cargo_run_signal"),
+    (CargoRunStop, cargo_run_stop, "cargo_run_stop")
 );
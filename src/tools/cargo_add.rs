@@ -1,15 +1,28 @@
 use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
+use crate::tools::cargo_utils::{
+    capture_output, command_limits, create_cargo_command, execute_cargo_command,
+    render_command_output, run_cargo_command,
+};
+use crate::tools::lockfile::DependencySnapshot;
+use crate::tools::manifest::Manifest;
+use crate::tools::metadata::load_metadata;
+use crate::tools::process::Limits;
 use anyhow::{Result, anyhow};
 use mcplease::{
     traits::{Tool, WithExamples},
     types::Example,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Write, fs, path::Path};
+use toml_edit::{InlineTable, Item, Value};
 
 /// Add dependencies to Cargo.toml using cargo add
-#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+///
+/// Dependencies can come from a registry, a local path or a git repository, and can be
+/// added as normal, dev, build or platform-specific dependencies. With workspace, they
+/// are added to the root manifest's [workspace.dependencies] and the member inherits
+/// them with `workspace = true`.
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
 #[serde(rename = "cargo_add")]
 pub struct CargoAdd {
     /// List of dependencies to add (e.g., ['serde', 'tokio@1.0'])
@@ -25,6 +38,16 @@ pub struct CargoAdd {
     #[arg(long)]
     pub dev: Option<bool>,
 
+    /// Add as build dependencies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub build: Option<bool>,
+
+    /// Add as dependencies only for this platform, e.g. 'cfg(unix)' or a target triple
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub target: Option<String>,
+
     /// Add as optional dependencies
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
@@ -35,11 +58,68 @@ pub struct CargoAdd {
     #[arg(long)]
     pub features: Option<Vec<String>>,
 
+    /// Disable the dependency's default features
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub no_default_features: Option<bool>,
+
+    /// Name to import the dependency under, for a single dependency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub rename: Option<String>,
+
+    /// Filesystem path to a local crate to add, relative to the working directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Git repository URL to add the dependency from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub git: Option<String>,
+
+    /// Git branch to use (with git)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub branch: Option<String>,
+
+    /// Git tag to use (with git)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// Git commit to use (with git)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub rev: Option<String>,
+
+    /// Registry to add the dependency from, as named in cargo's configuration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub registry: Option<String>,
+
+    /// Add to the workspace root's [workspace.dependencies], and have the package inherit
+    /// them with `workspace = true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub workspace: Option<bool>,
+
+    /// Show what would be changed without writing any manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub dry_run: Option<bool>,
+
     /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -53,96 +133,406 @@ impl WithExamples for CargoAdd {
                 description: "Add a simple dependency",
                 item: Self {
                     dependencies: vec!["serde".into()],
-                    package: None,
-                    dev: None,
-                    optional: None,
-                    features: None,
-                    toolchain: None,
-                    cargo_env: None,
+                    ..Self::default()
                 },
             },
             Example {
                 description: "Add multiple dependencies with versions",
                 item: Self {
                     dependencies: vec!["serde@1.0".into(), "tokio@1.0".into()],
-                    package: None,
-                    dev: None,
-                    optional: None,
-                    features: None,
-                    toolchain: None,
-                    cargo_env: None,
+                    ..Self::default()
                 },
             },
             Example {
                 description: "Add a dev dependency",
                 item: Self {
                     dependencies: vec!["criterion".into()],
-                    package: None,
                     dev: Some(true),
-                    optional: None,
-                    features: None,
-                    toolchain: None,
-                    cargo_env: None,
+                    ..Self::default()
                 },
             },
             Example {
                 description: "Add dependency with features",
                 item: Self {
                     dependencies: vec!["tokio".into()],
-                    package: None,
-                    dev: None,
-                    optional: None,
                     features: Some(vec!["full".into()]),
-                    toolchain: None,
-                    cargo_env: None,
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Add a crate from a git branch",
+                item: Self {
+                    dependencies: vec!["my-fork".into()],
+                    git: Some("https://github.com/me/my-fork".into()),
+                    branch: Some("fix".into()),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Add a unix-only build dependency without default features",
+                item: Self {
+                    dependencies: vec!["cc".into()],
+                    build: Some(true),
+                    target: Some("cfg(unix)".into()),
+                    no_default_features: Some(true),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Share a dependency through [workspace.dependencies]",
+                item: Self {
+                    dependencies: vec!["serde@1".into()],
+                    package: Some("my-lib".into()),
+                    features: Some(vec!["derive".into()]),
+                    workspace: Some(true),
+                    ..Self::default()
                 },
             },
         ]
     }
 }
 
-impl Tool<CargoTools> for CargoAdd {
-    fn execute(self, state: &mut CargoTools) -> Result<String> {
-        if self.dependencies.is_empty() {
-            return Err(anyhow!("No dependencies specified"));
+impl CargoAdd {
+    fn check_source(&self) -> Result<()> {
+        if self.path.is_some() && self.git.is_some() {
+            return Err(anyhow!("Pass either path or git, not both"));
         }
 
-        let project_path = state.ensure_rust_project(None)?;
-
-        // Use toolchain from args, session default, or none
-        let toolchain = self
-            .toolchain
-            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
-
+        let refs = [&self.branch, &self.tag, &self.rev];
+        if refs.iter().filter(|r| r.is_some()).count() > 1 {
+            return Err(anyhow!("Pass at most one of branch, tag and rev"));
+        }
+        if self.git.is_none() && refs.iter().any(|r| r.is_some()) {
+            return Err(anyhow!("branch, tag and rev can only be used with git"));
+        }
 
-        let mut args = vec!["add"];
+        if self.rename.is_some() && self.dependencies.len() > 1 {
+            return Err(anyhow!(
+                "rename can only be used when adding one dependency"
+            ));
+        }
 
-        if let Some(ref package) = self.package {
-            args.extend_from_slice(&["--package", package]);
+        if self.dev.unwrap_or(false) && self.build.unwrap_or(false) {
+            return Err(anyhow!("Pass either dev or build, not both"));
         }
 
+        Ok(())
+    }
+
+    /// Arguments that say where and how the package uses the dependency
+    fn push_member_args<'a>(&'a self, args: &mut Vec<&'a str>, features: &'a str) {
         if self.dev.unwrap_or(false) {
             args.push("--dev");
         }
 
+        if self.build.unwrap_or(false) {
+            args.push("--build");
+        }
+
+        if let Some(ref target) = self.target {
+            args.extend_from_slice(&["--target", target]);
+        }
+
         if self.optional.unwrap_or(false) {
             args.push("--optional");
         }
 
-        let features_str;
+        if !features.is_empty() {
+            args.extend_from_slice(&["--features", features]);
+        }
 
-        if let Some(ref features) = self.features
-            && !features.is_empty() {
-                features_str = features.join(",");
-                args.extend_from_slice(&["--features", &features_str]);
+        if let Some(ref rename) = self.rename {
+            args.extend_from_slice(&["--rename", rename]);
+        }
+
+        if self.dry_run.unwrap_or(false) {
+            args.push("--dry-run");
+        }
+    }
+
+    /// Arguments that say where the dependency comes from
+    fn push_source_args<'a>(&'a self, args: &mut Vec<&'a str>) {
+        let sources = [
+            ("--path", &self.path),
+            ("--git", &self.git),
+            ("--branch", &self.branch),
+            ("--tag", &self.tag),
+            ("--rev", &self.rev),
+            ("--registry", &self.registry),
+        ];
+        for (flag, value) in sources {
+            if let Some(value) = value {
+                args.extend_from_slice(&[flag, value]);
             }
+        }
+
+        if self.no_default_features.unwrap_or(false) {
+            args.push("--no-default-features");
+        }
+    }
+
+    /// Add the dependencies to [workspace.dependencies], then to the package with
+    /// `workspace = true`
+    fn add_to_workspace(
+        &self,
+        project_path: &Path,
+        toolchain: Option<&str>,
+        state: &mut CargoTools,
+    ) -> Result<String> {
+        let limits = command_limits(None, state)?;
+        let metadata = load_metadata(project_path, toolchain, true, &limits)?;
+        let root_manifest = metadata.workspace_root.join("Cargo.toml");
+        let mut manifest = Manifest::load(&root_manifest)?;
+        let dry_run = self.dry_run.unwrap_or(false);
+
+        let (added, existing) = self.add_workspace_entries(
+            &mut manifest,
+            &metadata.workspace_root,
+            project_path,
+            |name| self.latest_version(name, project_path, toolchain, &limits),
+        )?;
+
+        let mut result = "=== cargo add (workspace dependencies) ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}\n", project_path.display());
+        if !existing.is_empty() {
+            let _ = writeln!(
+                result,
+                "ℹ️  Already in [workspace.dependencies], so left as they are: {}\n",
+                existing.join(", ")
+            );
+        }
+        if !added.is_empty() && dry_run {
+            let _ = writeln!(
+                result,
+                "🔍 Would edit [workspace.dependencies]:\n{}",
+                manifest.diff("Cargo.toml")
+            );
+        } else if !added.is_empty() {
+            manifest.save(&root_manifest)?;
+            let _ = writeln!(
+                result,
+                "✅ Added to [workspace.dependencies]: {}\n",
+                added.join(", ")
+            );
+        }
+
+        // The package to inherit the dependencies: the one asked for, or else the one in
+        // the working directory
+        let member = match self.package.as_deref() {
+            Some(name) => Some(metadata.member(name)?),
+            None => metadata.select_member(None, project_path).ok(),
+        };
+        let Some(member) = member else {
+            result.push_str(
+                "ℹ️  No package inherits them yet; pass package to add them to a member.\n\n",
+            );
+            return Ok(result);
+        };
+        if dry_run {
+            let _ = writeln!(
+                result,
+                "🔍 {} would inherit them with `workspace = true`\n",
+                member.name
+            );
+            return Ok(result);
+        }
+
+        // cargo add uses the workspace entry for dependencies that have one
+        let features = self
+            .features
+            .as_ref()
+            .map(|f| f.join(","))
+            .unwrap_or_default();
+        let mut args = vec!["add", "--package", &member.name];
+        self.push_member_args(&mut args, &features);
+        args.extend(added.iter().chain(&existing));
+
+        let mut cmd = create_cargo_command(&args, toolchain, self.cargo_env.as_ref());
+        let output = run_cargo_command(
+            &mut cmd,
+            project_path,
+            "cargo add",
+            self.timeout_secs,
+            state,
+        )?;
+        result.push_str(&render_command_output(
+            &cmd,
+            project_path,
+            "cargo add",
+            &output,
+            state,
+        )?);
+
+        // Don't leave entries behind that nothing uses
+        if !output.success() && manifest.is_changed() {
+            fs::write(&root_manifest, &manifest.original)?;
+            result.push_str("↩️  Restored the workspace's Cargo.toml\n\n");
+        }
+
+        Ok(result)
+    }
+
+    /// Add entries to [workspace.dependencies] for the dependencies it doesn't have yet,
+    /// looking up the latest version of registry dependencies given without one. Returns
+    /// the names of the dependencies that were added and of those that already had entries.
+    pub(crate) fn add_workspace_entries(
+        &self,
+        manifest: &mut Manifest,
+        workspace_root: &Path,
+        project_path: &Path,
+        mut latest_version: impl FnMut(&str) -> Result<String>,
+    ) -> Result<(Vec<&str>, Vec<&str>)> {
+        let mut added = vec![];
+        let mut existing = vec![];
+        for spec in &self.dependencies {
+            let (name, version) = match spec.split_once('@') {
+                Some((name, version)) => (name, Some(version.to_string())),
+                None => (spec.as_str(), None),
+            };
+            // Other members may already inherit an existing entry, so it is left as it is
+            if manifest.workspace_dependencies()?.contains_key(name) {
+                existing.push(name);
+                continue;
+            }
+            let version = match version {
+                Some(version) => Some(version),
+                None if self.path.is_none() && self.git.is_none() => Some(latest_version(name)?),
+                None => None,
+            };
+            let entry = self.workspace_entry(version, workspace_root, project_path);
+            manifest.workspace_dependencies()?.insert(name, entry);
+            added.push(name);
+        }
+        Ok((added, existing))
+    }
+
+    /// The [workspace.dependencies] entry for a dependency: just its version, or a table
+    /// with its source
+    pub(crate) fn workspace_entry(
+        &self,
+        version: Option<String>,
+        workspace_root: &Path,
+        project_path: &Path,
+    ) -> Item {
+        let path = self.path.as_ref().map(|path| {
+            // Paths in the root manifest are relative to the workspace root
+            let absolute = project_path.join(path);
+            let absolute = absolute.canonicalize().unwrap_or(absolute);
+            absolute
+                .strip_prefix(workspace_root)
+                .map(|relative| relative.display().to_string())
+                .unwrap_or_else(|_| absolute.display().to_string())
+        });
+
+        let mut table = InlineTable::new();
+        let fields = [
+            ("version", version),
+            ("path", path),
+            ("git", self.git.clone()),
+            ("branch", self.branch.clone()),
+            ("tag", self.tag.clone()),
+            ("rev", self.rev.clone()),
+            ("registry", self.registry.clone()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                table.insert(key, Value::from(value));
+            }
+        }
+        if self.no_default_features.unwrap_or(false) {
+            table.insert("default-features", Value::from(false));
+        }
+
+        match (table.len(), table.get("version")) {
+            (1, Some(version)) => Item::Value(version.clone()),
+            _ => Item::Value(Value::InlineTable(table)),
+        }
+    }
+
+    /// The newest published version of a crate, from cargo info
+    fn latest_version(
+        &self,
+        name: &str,
+        project_path: &Path,
+        toolchain: Option<&str>,
+        limits: &Limits,
+    ) -> Result<String> {
+        let mut args = vec!["info", name];
+        if let Some(ref registry) = self.registry {
+            args.extend_from_slice(&["--registry", registry]);
+        }
+        let mut cmd = create_cargo_command(&args, toolchain, self.cargo_env.as_ref());
+        let output = capture_output(&mut cmd, project_path, limits)?;
+        if !output.success() {
+            return Err(anyhow!(
+                "Could not look up {name}: {}",
+                output.stderr.trim()
+            ));
+        }
+
+        // `version: 1.0.109 (latest 2.0.104)` when the lockfile has an older version
+        let line = output
+            .stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("version: "))
+            .ok_or_else(|| anyhow!("cargo info did not report a version for {name}"))?;
+        let latest = line
+            .split_once("(latest ")
+            .and_then(|(_, latest)| latest.split([')', ' ']).next());
+        Ok(latest
+            .or_else(|| line.split_whitespace().next())
+            .unwrap_or(line)
+            .to_string())
+    }
+}
+
+impl Tool<CargoTools> for CargoAdd {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        if self.dependencies.is_empty() {
+            return Err(anyhow!("No dependencies specified"));
+        }
+        self.check_source()?;
+
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .clone()
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        // Snapshot the lockfile and manifests to report what changed
+        let snapshot = if self.dry_run.unwrap_or(false) {
+            None
+        } else {
+            DependencySnapshot::take(&project_path, toolchain.as_deref(), &limits).ok()
+        };
+
+        let mut result = if self.workspace.unwrap_or(false) {
+            self.add_to_workspace(&project_path, toolchain.as_deref(), state)?
+        } else {
+            let features = self
+                .features
+                .as_ref()
+                .map(|f| f.join(","))
+                .unwrap_or_default();
+            let mut args = vec!["add"];
+            if let Some(ref package) = self.package {
+                args.extend_from_slice(&["--package", package]);
+            }
+            self.push_member_args(&mut args, &features);
+            self.push_source_args(&mut args);
+            args.extend(self.dependencies.iter().map(String::as_str));
+
+            let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+            execute_cargo_command(cmd, &project_path, "cargo add", self.timeout_secs, state)?
+        };
 
-        // Add the dependencies
-        for dep in &self.dependencies {
-            args.push(dep);
+        if let Some(snapshot) = snapshot {
+            result.push_str(&snapshot.report());
         }
 
-        let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo add")
+        Ok(result)
     }
 }
@@ -0,0 +1,294 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{command_limits, workspace_root};
+use crate::tools::diagnostics::{Diagnostic, Suggestion, plural};
+use crate::tools::suggestions::{EditedFile, resolve};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    fmt::Write,
+};
+
+/// Apply selected suggestions from the most recent cargo_check or cargo_clippy run
+///
+/// Suggested replacements are edited directly into the source files, and a unified diff
+/// of the changes is returned. Select suggestions by diagnostic id, lint name and/or file;
+/// when several selectors are given, a diagnostic must match all of them. Only
+/// machine-applicable suggestions are applied unless include_maybe_incorrect is set.
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_apply_suggestions")]
+pub struct CargoApplySuggestions {
+    /// Diagnostic ids to apply suggestions for, as reported by cargo_check or cargo_clippy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub ids: Option<Vec<usize>>,
+
+    /// Lint names or error codes to apply suggestions for (e.g. 'clippy::needless_borrow')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub lints: Option<Vec<String>>,
+
+    /// Files to apply suggestions in, as reported in diagnostics (e.g. 'src/main.rs')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub files: Option<Vec<String>>,
+
+    /// Also apply suggestions the compiler marks as MaybeIncorrect
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub include_maybe_incorrect: Option<bool>,
+
+    /// Show the diff without modifying any files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub dry_run: Option<bool>,
+}
+
+impl WithExamples for CargoApplySuggestions {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Apply the suggestions for two specific diagnostics",
+                item: Self {
+                    ids: Some(vec![2, 5]),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Apply every needless_borrow fix",
+                item: Self {
+                    lints: Some(vec!["clippy::needless_borrow".into()]),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Preview all fixes in one file without writing them",
+                item: Self {
+                    files: Some(vec!["src/lib.rs".into()]),
+                    dry_run: Some(true),
+                    ..Self::default()
+                },
+            },
+        ]
+    }
+}
+
+impl CargoApplySuggestions {
+    pub(crate) fn selects(&self, diagnostic: &Diagnostic) -> bool {
+        let id_matches = self
+            .ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&diagnostic.id));
+
+        let lint_matches = self
+            .lints
+            .as_ref()
+            .is_none_or(|lints| lints.iter().any(|lint| lint == diagnostic.lint()));
+
+        let file_matches = self.files.as_ref().is_none_or(|files| {
+            files.iter().any(|file| {
+                diagnostic
+                    .primary
+                    .as_ref()
+                    .is_some_and(|primary| &primary.file == file)
+                    || diagnostic
+                        .suggestions
+                        .iter()
+                        .flat_map(|suggestion| &suggestion.edits)
+                        .any(|edit| &edit.file == file)
+            })
+        });
+
+        id_matches && lint_matches && file_matches
+    }
+
+    pub(crate) fn eligible(&self, suggestion: &Suggestion) -> bool {
+        suggestion.is_machine_applicable()
+            || (self.include_maybe_incorrect.unwrap_or(false)
+                && suggestion.applicability == "MaybeIncorrect")
+    }
+}
+
+impl Tool<CargoTools> for CargoApplySuggestions {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        if self.ids.is_none() && self.lints.is_none() && self.files.is_none() {
+            return Err(anyhow!(
+                "Select suggestions to apply with ids, lints and/or files"
+            ));
+        }
+
+        let project_path = state.ensure_rust_project(None)?;
+        let captured = state.get_last_diagnostics(None)?.ok_or_else(|| {
+            anyhow!("No diagnostics captured yet. Run cargo_check or cargo_clippy first.")
+        })?;
+
+        if captured.project_path != project_path {
+            return Err(anyhow!(
+                "Diagnostics were captured in {}, but the working directory is now {}. \
+                 Run cargo_check or cargo_clippy again.",
+                captured.project_path.display(),
+                project_path.display()
+            ));
+        }
+
+        let toolchain = state.get_default_toolchain(None)?;
+        let limits = command_limits(None, state)?;
+        let root = workspace_root(&project_path, toolchain.as_deref(), &limits)?;
+        let dry_run = self.dry_run.unwrap_or(false);
+
+        let mut files: BTreeMap<String, EditedFile> = BTreeMap::new();
+        let mut applied = vec![];
+        let mut skipped = vec![];
+
+        for diagnostic in captured.diagnostics.iter().filter(|d| self.selects(d)) {
+            // Alternative suggestions for one diagnostic generally conflict, so only the
+            // first eligible one is used
+            let Some(suggestion) = diagnostic.suggestions.iter().find(|s| self.eligible(s)) else {
+                skipped.push((diagnostic, "no applicable suggestion".to_string()));
+                continue;
+            };
+
+            let problem = suggestion.edits.iter().find_map(|edit| {
+                let file = match files.entry(edit.file.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => match resolve(&root, &edit.file)
+                        .and_then(|path| EditedFile::load(path).map_err(|e| e.to_string()))
+                    {
+                        Ok(file) => entry.insert(file),
+                        Err(problem) => return Some(problem),
+                    },
+                };
+                file.check(edit).err()
+            });
+
+            if let Some(problem) = problem {
+                skipped.push((diagnostic, problem));
+                continue;
+            }
+
+            for edit in &suggestion.edits {
+                let file = files.get_mut(&edit.file).expect("loaded above");
+                if !file.is_duplicate(edit) {
+                    file.edits.push(edit.clone());
+                }
+            }
+            applied.push((diagnostic, suggestion));
+        }
+
+        let mut result = String::from("=== apply suggestions ===\n");
+        let _ = writeln!(
+            result,
+            "📁 Working directory: {}\n🔧 Diagnostics from: {}\n",
+            project_path.display(),
+            captured.command
+        );
+
+        if applied.is_empty() && skipped.is_empty() {
+            result.push_str("ℹ️  No captured diagnostics match the selection\n");
+            return Ok(result);
+        }
+
+        let changed: Vec<_> = files.values().filter(|f| !f.edits.is_empty()).collect();
+        let summary = format!(
+            "{} to {}",
+            plural(applied.len(), "suggestion", "suggestions"),
+            plural(changed.len(), "file", "files")
+        );
+        if dry_run {
+            let _ = writeln!(result, "🔍 Dry run: would apply {summary}");
+        } else {
+            let _ = writeln!(result, "✅ Applied {summary}");
+        }
+
+        for (diagnostic, suggestion) in &applied {
+            let _ = writeln!(
+                result,
+                "  [{}] {}: {}",
+                diagnostic.id,
+                diagnostic.lint(),
+                suggestion.message
+            );
+        }
+        result.push('\n');
+
+        if !skipped.is_empty() {
+            result.push_str("⚠️  Skipped:\n");
+            for (diagnostic, reason) in &skipped {
+                let _ = writeln!(
+                    result,
+                    "  [{}] {}: {reason}",
+                    diagnostic.id,
+                    diagnostic.lint()
+                );
+            }
+            result.push('\n');
+        }
+
+        if changed.is_empty() {
+            return Ok(result);
+        }
+
+        result.push_str("📝 Diff:\n");
+        let mut edited_files = BTreeMap::new();
+        for (name, file) in files.iter().filter(|(_, f)| !f.edits.is_empty()) {
+            let edited = file.edited();
+            let relative = file
+                .path
+                .strip_prefix(&root)
+                .unwrap_or(&file.path)
+                .display()
+                .to_string();
+            let diff = TextDiff::from_lines(&file.original, &edited);
+            let _ = write!(
+                result,
+                "{}",
+                diff.unified_diff()
+                    .header(&format!("a/{relative}"), &format!("b/{relative}"))
+            );
+            edited_files.insert(name.as_str(), edited);
+        }
+
+        if dry_run {
+            return Ok(result);
+        }
+
+        for (name, edited) in &edited_files {
+            let path = &files[*name].path;
+            std::fs::write(path, edited)
+                .map_err(|e| anyhow!("Could not write {}: {e}", path.display()))?;
+        }
+
+        // Keep the remaining suggestions usable by moving their offsets past the edits
+        // that were just made. Applied diagnostics and suggestions whose text was
+        // rewritten are dropped.
+        let applied_ids: BTreeSet<usize> = applied.iter().map(|(d, _)| d.id).collect();
+        let mut remaining = captured.clone();
+        remaining
+            .diagnostics
+            .retain(|diagnostic| !applied_ids.contains(&diagnostic.id));
+        for diagnostic in &mut remaining.diagnostics {
+            diagnostic.suggestions.retain_mut(|suggestion| {
+                suggestion.edits.iter_mut().all(|edit| {
+                    let Some(edited) = edited_files.get(edit.file.as_str()) else {
+                        return true;
+                    };
+                    match files[&edit.file].rebase(edit, edited) {
+                        Some(rebased) => {
+                            *edit = rebased;
+                            true
+                        }
+                        None => false,
+                    }
+                })
+            });
+        }
+        state.set_last_diagnostics(Some(remaining), None)?;
+
+        Ok(result)
+    }
+}
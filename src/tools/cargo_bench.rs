@@ -1,6 +1,9 @@
 use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
-use anyhow::Result;
+use crate::tools::cargo_utils::{
+    create_cargo_command, execute_cargo_command, render_command_output, start_background_job,
+};
+use crate::tools::compile_options::CompileOptions;
+use anyhow::{Result, anyhow};
 use mcplease::{
     traits::{Tool, WithExamples},
     types::Example,
@@ -17,6 +20,10 @@ pub struct CargoBench {
     #[arg(long)]
     pub package: Option<String>,
 
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: CompileOptions,
+
     /// Optional specific benchmark name to run
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
@@ -32,6 +39,19 @@ pub struct CargoBench {
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Run in the background and return a job id immediately, to be followed with
+    /// job_status, job_output and job_kill. Background jobs have no timeout unless
+    /// timeout_secs is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub background: Option<bool>,
+
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -45,9 +65,12 @@ impl WithExamples for CargoBench {
                 description: "Run all benchmarks",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     bench_name: None,
                     baseline: None,
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -55,9 +78,12 @@ impl WithExamples for CargoBench {
                 description: "Run a specific benchmark",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     bench_name: Some("my_benchmark".into()),
                     baseline: None,
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -65,9 +91,12 @@ impl WithExamples for CargoBench {
                 description: "Run benchmarks for a specific package",
                 item: Self {
                     package: Some("my-lib".into()),
+                    options: CompileOptions::default(),
                     bench_name: None,
                     baseline: None,
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -75,9 +104,12 @@ impl WithExamples for CargoBench {
                 description: "Run benchmarks with a baseline for comparison",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     bench_name: None,
                     baseline: Some("main".into()),
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -88,18 +120,27 @@ impl WithExamples for CargoBench {
 impl Tool<CargoTools> for CargoBench {
     fn execute(self, state: &mut CargoTools) -> Result<String> {
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
 
-
         let mut args = vec!["bench"];
-        
+
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
+        // cargo bench has no --release flag, as benchmarks always build optimized
+        if self.options.release.unwrap_or(false) {
+            return Err(anyhow!(
+                "cargo bench always builds with the optimized bench profile, so release \
+                 isn't supported. Use profile to pick another profile."
+            ));
+        }
+        self.options.push_args(&mut args)?;
+
         if let Some(ref bench_name) = self.bench_name {
             args.push(bench_name);
         }
@@ -109,6 +150,20 @@ impl Tool<CargoTools> for CargoBench {
         }
 
         let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo bench")
+        if self.background.unwrap_or(false) {
+            return start_background_job(
+                cmd,
+                &project_path,
+                "cargo bench",
+                None,
+                self.timeout_secs,
+                state,
+                |cmd, project_path, output, state| {
+                    render_command_output(cmd, project_path, "cargo bench", output, state)
+                },
+            );
+        }
+
+        execute_cargo_command(cmd, &project_path, "cargo bench", self.timeout_secs, state)
     }
 }
@@ -1,5 +1,8 @@
 use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
+use crate::tools::cargo_utils::{
+    create_cargo_command, execute_cargo_command, render_command_output, start_background_job,
+};
+use crate::tools::compile_options::CompileOptions;
 use anyhow::Result;
 use mcplease::{
     traits::{Tool, WithExamples},
@@ -17,16 +20,28 @@ pub struct CargoBuild {
     #[arg(long)]
     pub package: Option<String>,
 
-    /// Build in release mode
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[arg(long)]
-    pub release: Option<bool>,
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: CompileOptions,
 
     /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Run in the background and return a job id immediately, to be followed with
+    /// job_status, job_output and job_kill. Background jobs have no timeout unless
+    /// timeout_secs is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub background: Option<bool>,
+
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -40,8 +55,10 @@ impl WithExamples for CargoBuild {
                 description: "Build the project in debug mode",
                 item: Self {
                     package: None,
-                    release: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -49,8 +66,13 @@ impl WithExamples for CargoBuild {
                 description: "Build the project in release mode",
                 item: Self {
                     package: None,
-                    release: Some(true),
+                    options: CompileOptions {
+                        release: Some(true),
+                        ..CompileOptions::default()
+                    },
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -58,8 +80,10 @@ impl WithExamples for CargoBuild {
                 description: "Build a specific package",
                 item: Self {
                     package: Some("my-lib".into()),
-                    release: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
+                    background: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -67,8 +91,24 @@ impl WithExamples for CargoBuild {
                 description: "Build with nightly toolchain",
                 item: Self {
                     package: None,
-                    release: None,
+                    options: CompileOptions::default(),
                     toolchain: Some("nightly".into()),
+                    background: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Start a release build as a background job",
+                item: Self {
+                    package: None,
+                    options: CompileOptions {
+                        release: Some(true),
+                        ..CompileOptions::default()
+                    },
+                    toolchain: None,
+                    background: Some(true),
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -79,23 +119,35 @@ impl WithExamples for CargoBuild {
 impl Tool<CargoTools> for CargoBuild {
     fn execute(self, state: &mut CargoTools) -> Result<String> {
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
 
-
         let mut args = vec!["build"];
-        
+
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
-        if self.release.unwrap_or(false) {
-            args.push("--release");
-        }
+        self.options.push_args(&mut args)?;
 
         let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo build")
+        if self.background.unwrap_or(false) {
+            return start_background_job(
+                cmd,
+                &project_path,
+                "cargo build",
+                None,
+                self.timeout_secs,
+                state,
+                |cmd, project_path, output, state| {
+                    render_command_output(cmd, project_path, "cargo build", output, state)
+                },
+            );
+        }
+
+        execute_cargo_command(cmd, &project_path, "cargo build", self.timeout_secs, state)
     }
 }
@@ -1,5 +1,10 @@
-use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
+use crate::state::{CapturedDiagnostics, CargoTools};
+use crate::tools::cargo_utils::{
+    create_cargo_command, format_command_header, push_output, push_report, run_cargo_command,
+};
+use crate::tools::compile_options::CompileOptions;
+use crate::tools::diagnostics::{parse_diagnostics, render_diagnostics};
+use crate::tools::output::denoise;
 use anyhow::Result;
 use mcplease::{
     traits::{Tool, WithExamples},
@@ -9,6 +14,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Run cargo check to verify the code compiles
+///
+/// Compiler output is parsed into a list of diagnostics (level, code, message, location,
+/// related spans and attached notes) instead of rustc's human-readable rendering.
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
 #[serde(rename = "cargo_check")]
 pub struct CargoCheck {
@@ -17,11 +25,26 @@ pub struct CargoCheck {
     #[arg(long)]
     pub package: Option<String>,
 
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: CompileOptions,
+
     /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
+    /// Also list the diagnostics as JSON, one object per line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub json: Option<bool>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -35,7 +58,10 @@ impl WithExamples for CargoCheck {
                 description: "Basic cargo check in current project",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -43,7 +69,26 @@ impl WithExamples for CargoCheck {
                 description: "Check a specific package in a workspace",
                 item: Self {
                     package: Some("my-lib".into()),
+                    options: CompileOptions::default(),
+                    toolchain: None,
+                    timeout_secs: None,
+                    json: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Check without default features for a wasm target",
+                item: Self {
+                    package: None,
+                    options: CompileOptions {
+                        no_default_features: Some(true),
+                        features: Some("web".into()),
+                        target: Some("wasm32-unknown-unknown".into()),
+                        ..CompileOptions::default()
+                    },
                     toolchain: None,
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -51,7 +96,10 @@ impl WithExamples for CargoCheck {
                 description: "Check using nightly toolchain",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     toolchain: Some("nightly".into()),
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -59,7 +107,10 @@ impl WithExamples for CargoCheck {
                 description: "Check with custom environment variables",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: Some(
                         [
                             ("RUSTFLAGS".into(), "-D warnings".into()),
@@ -82,13 +133,53 @@ impl Tool<CargoTools> for CargoCheck {
             .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
 
-        let mut args = vec!["check"];
+        let mut args = vec!["check", "--message-format=json"];
 
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
-        let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo check")
+        self.options.push_args(&mut args)?;
+
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+        let output = run_cargo_command(
+            &mut cmd,
+            &project_path,
+            "cargo check",
+            self.timeout_secs,
+            state,
+        )?;
+        let diagnostics = parse_diagnostics(&output.stdout);
+        state.set_last_diagnostics(
+            Some(CapturedDiagnostics {
+                command: "cargo check".into(),
+                project_path: project_path.clone(),
+                diagnostics: diagnostics.clone(),
+            }),
+            None,
+        )?;
+
+        let budget = state.get_output_budget(None)?;
+        let mut result = format_command_header(&cmd, &project_path, "cargo check", &output);
+        push_report(
+            &mut result,
+            &render_diagnostics(&diagnostics, self.json.unwrap_or(false)),
+            budget,
+            output.run_id,
+        );
+
+        // Failures that aren't compiler errors (bad manifests, resolution failures, ...)
+        // are only reported on stderr
+        if !output.success() && !diagnostics.iter().any(|d| d.is_error()) {
+            push_output(
+                &mut result,
+                "📤 STDERR:",
+                &denoise(&output.stderr),
+                budget,
+                output.run_id,
+            );
+        }
+
+        Ok(result)
     }
 }
@@ -22,6 +22,12 @@ pub struct CargoClean {
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -36,6 +42,7 @@ impl WithExamples for CargoClean {
                 item: Self {
                     package: None,
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -44,6 +51,7 @@ impl WithExamples for CargoClean {
                 item: Self {
                     package: Some("my-lib".into()),
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -54,19 +62,19 @@ impl WithExamples for CargoClean {
 impl Tool<CargoTools> for CargoClean {
     fn execute(self, state: &mut CargoTools) -> Result<String> {
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
 
-
         let mut args = vec!["clean"];
-        
+
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
         let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo clean")
+        execute_cargo_command(cmd, &project_path, "cargo clean", self.timeout_secs, state)
     }
 }
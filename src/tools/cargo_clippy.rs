@@ -1,5 +1,12 @@
-use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
+use crate::state::{CapturedDiagnostics, CargoTools};
+use crate::tools::cargo_utils::{
+    create_cargo_command, execute_cargo_command, format_command_header, push_output, push_report,
+    run_cargo_command,
+};
+use crate::tools::compile_options::CompileOptions;
+use crate::tools::diagnostics::{parse_diagnostics, render_lint_groups};
+use crate::tools::lints::lint_args;
+use crate::tools::output::denoise;
 use anyhow::Result;
 use mcplease::{
     traits::{Tool, WithExamples},
@@ -9,6 +16,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Run cargo clippy for linting suggestions
+///
+/// Findings are grouped by lint name with per-lint and per-file counts, and any
+/// suggested replacement code is attached to each finding. Warnings are denied unless lint
+/// levels are given with allow, warn or deny, which take lints and lint groups such as
+/// 'clippy::pedantic'; use cargo_lints to keep lint levels in Cargo.toml instead.
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
 #[serde(rename = "cargo_clippy")]
 pub struct CargoClippy {
@@ -17,6 +29,10 @@ pub struct CargoClippy {
     #[arg(long)]
     pub package: Option<String>,
 
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: CompileOptions,
+
     /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
@@ -27,6 +43,33 @@ pub struct CargoClippy {
     #[arg(long)]
     pub fix: Option<bool>,
 
+    /// Lints or lint groups to allow for this run, e.g. 'clippy::too_many_lines'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub allow: Option<Vec<String>>,
+
+    /// Lints or lint groups to warn about for this run, e.g. 'clippy::pedantic'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub warn: Option<Vec<String>>,
+
+    /// Lints or lint groups to deny for this run, e.g. 'warnings' or 'clippy::unwrap_used'.
+    /// Defaults to 'warnings' when no lint levels are given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub deny: Option<Vec<String>>,
+
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
+    /// Also list the diagnostics as JSON, one object per line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub json: Option<bool>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -40,8 +83,14 @@ impl WithExamples for CargoClippy {
                 description: "Basic cargo clippy in current project",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
                     fix: None,
+                    allow: None,
+                    warn: None,
+                    deny: None,
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -49,8 +98,48 @@ impl WithExamples for CargoClippy {
                 description: "Run clippy on a specific package",
                 item: Self {
                     package: Some("my-lib".into()),
+                    options: CompileOptions::default(),
+                    toolchain: None,
+                    fix: None,
+                    allow: None,
+                    warn: None,
+                    deny: None,
+                    timeout_secs: None,
+                    json: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Lint every target of every package in the workspace",
+                item: Self {
+                    package: None,
+                    options: CompileOptions {
+                        workspace: Some(true),
+                        all_targets: Some(true),
+                        ..CompileOptions::default()
+                    },
+                    toolchain: None,
+                    fix: None,
+                    allow: None,
+                    warn: None,
+                    deny: None,
+                    timeout_secs: None,
+                    json: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Check pedantic lints, except one, without failing on warnings",
+                item: Self {
+                    package: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
                     fix: None,
+                    allow: Some(vec!["clippy::module_name_repetitions".into()]),
+                    warn: Some(vec!["clippy::pedantic".into()]),
+                    deny: Some(vec![]),
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -58,8 +147,14 @@ impl WithExamples for CargoClippy {
                 description: "Run clippy with automatic fixes",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     toolchain: None,
                     fix: Some(true),
+                    allow: None,
+                    warn: None,
+                    deny: None,
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -67,8 +162,14 @@ impl WithExamples for CargoClippy {
                 description: "Run clippy with nightly toolchain",
                 item: Self {
                     package: None,
+                    options: CompileOptions::default(),
                     toolchain: Some("nightly".into()),
                     fix: None,
+                    allow: None,
+                    warn: None,
+                    deny: None,
+                    timeout_secs: None,
+                    json: None,
                     cargo_env: None,
                 },
             },
@@ -79,26 +180,91 @@ impl WithExamples for CargoClippy {
 impl Tool<CargoTools> for CargoClippy {
     fn execute(self, state: &mut CargoTools) -> Result<String> {
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
 
+        let fix = self.fix.unwrap_or(false);
 
         let mut args = vec!["clippy"];
-        
+
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
-        if self.fix.unwrap_or(false) {
+        self.options.push_args(&mut args)?;
+
+        if fix {
             args.push("--fix");
+        } else {
+            args.push("--message-format=json");
         }
 
-        // Add clippy arguments
-        args.extend_from_slice(&["--", "-D", "warnings"]);
+        // Lint levels for clippy, denying warnings unless told otherwise
+        let lint_flags = match (&self.allow, &self.warn, &self.deny) {
+            (None, None, None) => vec!["-D".to_string(), "warnings".to_string()],
+            (allow, warn, deny) => lint_args(
+                allow.as_deref().unwrap_or_default(),
+                warn.as_deref().unwrap_or_default(),
+                deny.as_deref().unwrap_or_default(),
+            ),
+        };
+        if !lint_flags.is_empty() {
+            args.push("--");
+            args.extend(lint_flags.iter().map(String::as_str));
+        }
+
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+
+        // cargo fix reports what it changed in prose; there are no diagnostics to group
+        if fix {
+            return execute_cargo_command(
+                cmd,
+                &project_path,
+                "cargo clippy",
+                self.timeout_secs,
+                state,
+            );
+        }
+
+        let output = run_cargo_command(
+            &mut cmd,
+            &project_path,
+            "cargo clippy",
+            self.timeout_secs,
+            state,
+        )?;
+        let diagnostics = parse_diagnostics(&output.stdout);
+        state.set_last_diagnostics(
+            Some(CapturedDiagnostics {
+                command: "cargo clippy".into(),
+                project_path: project_path.clone(),
+                diagnostics: diagnostics.clone(),
+            }),
+            None,
+        )?;
+
+        let budget = state.get_output_budget(None)?;
+        let mut result = format_command_header(&cmd, &project_path, "cargo clippy", &output);
+        push_report(
+            &mut result,
+            &render_lint_groups(&diagnostics, self.json.unwrap_or(false)),
+            budget,
+            output.run_id,
+        );
+
+        if !output.success() && !diagnostics.iter().any(|d| d.is_error()) {
+            push_output(
+                &mut result,
+                "📤 STDERR:",
+                &denoise(&output.stderr),
+                budget,
+                output.run_id,
+            );
+        }
 
-        let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo clippy")
+        Ok(result)
     }
 }
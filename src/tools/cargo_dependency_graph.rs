@@ -0,0 +1,342 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::command_limits;
+use crate::tools::diagnostics::plural;
+use crate::tools::metadata::{Metadata, load_metadata};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write,
+};
+
+/// Graph description language to export to
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart, which renders in GitHub markdown
+    Mermaid,
+}
+
+/// Export the resolved dependency graph as Graphviz DOT or Mermaid
+///
+/// Builds the graph from cargo metadata, starting at the workspace members (or the given
+/// package). Workspace members are drawn in bold, and build and dev dependencies as dashed
+/// edges. The graph can be narrowed to workspace members only, to a depth, or to the paths
+/// between two crates.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_dependency_graph")]
+pub struct CargoDependencyGraph {
+    /// Output format: 'mermaid' (default) or 'dot'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub format: Option<GraphFormat>,
+
+    /// Crate to start the graph from, as 'name' or 'name@version' (defaults to every
+    /// workspace member)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// Only keep the paths that lead from the starting crates to this crate, as 'name' or
+    /// 'name@version'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Only show workspace members and the dependencies between them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub workspace_only: Option<bool>,
+
+    /// Maximum number of dependency levels to follow from the starting crates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub depth: Option<usize>,
+
+    /// Include dev-dependencies of workspace members
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub include_dev: Option<bool>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoDependencyGraph {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Mermaid diagram of how the workspace members depend on each other",
+                item: Self {
+                    format: None,
+                    from: None,
+                    to: None,
+                    workspace_only: Some(true),
+                    depth: None,
+                    include_dev: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "DOT graph of a package and its direct dependencies",
+                item: Self {
+                    format: Some(GraphFormat::Dot),
+                    from: Some("my-app".into()),
+                    to: None,
+                    workspace_only: None,
+                    depth: Some(1),
+                    include_dev: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Every path from a package to syn 1",
+                item: Self {
+                    format: None,
+                    from: Some("my-app".into()),
+                    to: Some("syn@1".into()),
+                    workspace_only: None,
+                    depth: None,
+                    include_dev: None,
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+/// A dependency edge in the exported graph
+struct GraphEdge<'a> {
+    from: &'a str,
+    to: &'a str,
+    /// `build` or `dev` when the dependency is only used as one; drawn dashed and labelled
+    kind: Option<&'a str>,
+}
+
+impl Tool<CargoTools> for CargoDependencyGraph {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), false, &limits)?;
+        let roots: Vec<&str> = match self.from.as_deref() {
+            Some(spec) => metadata
+                .find_packages(spec)?
+                .into_iter()
+                .map(|package| package.id.as_str())
+                .collect(),
+            None => metadata
+                .members()
+                .map(|package| package.id.as_str())
+                .collect(),
+        };
+
+        let include_dev = self.include_dev.unwrap_or(false);
+        let workspace_only = self.workspace_only.unwrap_or(false);
+        let mut edges = reachable_edges(&metadata, &roots, self.depth, |id, kind| {
+            (include_dev || kind != Some("dev")) && (!workspace_only || metadata.is_member(id))
+        });
+
+        let mut nodes: BTreeSet<&str> = roots.iter().copied().collect();
+        if let Some(ref spec) = self.to {
+            let targets: Vec<&str> = metadata
+                .find_packages(spec)?
+                .into_iter()
+                .map(|package| package.id.as_str())
+                .collect();
+            nodes = leading_to(&edges, &targets);
+            if !roots.iter().any(|root| nodes.contains(root)) {
+                return Err(anyhow!(
+                    "{spec} is not reachable from {} with these options",
+                    self.from.as_deref().unwrap_or("the workspace members")
+                ));
+            }
+            edges.retain(|edge| nodes.contains(edge.from) && nodes.contains(edge.to));
+        } else {
+            nodes.extend(edges.iter().flat_map(|edge| [edge.from, edge.to]));
+        }
+
+        let labels: BTreeMap<&str, String> = nodes
+            .iter()
+            .map(|id| {
+                let label = metadata
+                    .package(id)
+                    .map_or_else(|| id.to_string(), |p| format!("{} v{}", p.name, p.version));
+                (*id, label)
+            })
+            .collect();
+        let is_member = |id: &str| metadata.is_member(id);
+
+        let format = self.format.unwrap_or(GraphFormat::Mermaid);
+        let graph = match format {
+            GraphFormat::Dot => render_dot(&labels, &edges, is_member),
+            GraphFormat::Mermaid => render_mermaid(&labels, &edges, is_member),
+        };
+
+        let mut result = "=== cargo dependency graph ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}", project_path.display());
+        let _ = writeln!(
+            result,
+            "📊 {} and {}\n",
+            plural(nodes.len(), "crate", "crates"),
+            plural(edges.len(), "dependency", "dependencies")
+        );
+        let language = match format {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Mermaid => "mermaid",
+        };
+        let _ = writeln!(result, "```{language}\n{graph}```");
+
+        Ok(result)
+    }
+}
+
+/// Edges reachable from `roots` in the resolved graph, following at most `depth` levels.
+/// `follow` decides, from a dependency's package id and kind, whether to include it.
+fn reachable_edges<'a>(
+    metadata: &'a Metadata,
+    roots: &[&'a str],
+    depth: Option<usize>,
+    follow: impl Fn(&str, Option<&str>) -> bool,
+) -> Vec<GraphEdge<'a>> {
+    let Some(resolve) = &metadata.resolve else {
+        return vec![];
+    };
+
+    let mut edges = vec![];
+    let mut seen: BTreeSet<&str> = roots.iter().copied().collect();
+    let mut queue: VecDeque<(&str, usize)> = roots.iter().map(|root| (*root, 0)).collect();
+    while let Some((id, level)) = queue.pop_front() {
+        if depth.is_some_and(|depth| level >= depth) {
+            continue;
+        }
+        let Some(node) = resolve.nodes.iter().find(|node| node.id == id) else {
+            continue;
+        };
+
+        for dep in &node.deps {
+            let kinds: Vec<Option<&str>> = dep
+                .dep_kinds
+                .iter()
+                .map(|dep_kind| dep_kind.kind.as_deref())
+                .filter(|kind| follow(&dep.pkg, *kind))
+                .collect();
+            if kinds.is_empty() {
+                continue;
+            }
+
+            edges.push(GraphEdge {
+                from: id,
+                to: &dep.pkg,
+                kind: if kinds.contains(&None) {
+                    None
+                } else {
+                    kinds[0]
+                },
+            });
+            if seen.insert(&dep.pkg) {
+                queue.push_back((&dep.pkg, level + 1));
+            }
+        }
+    }
+    edges
+}
+
+/// The nodes from which one of `targets` can be reached, including the targets
+fn leading_to<'a>(edges: &[GraphEdge<'a>], targets: &[&'a str]) -> BTreeSet<&'a str> {
+    let mut nodes: BTreeSet<&str> = targets.iter().copied().collect();
+    let mut queue: VecDeque<&str> = targets.iter().copied().collect();
+    while let Some(id) = queue.pop_front() {
+        for edge in edges.iter().filter(|edge| edge.to == id) {
+            if nodes.insert(edge.from) {
+                queue.push_back(edge.from);
+            }
+        }
+    }
+    nodes
+}
+
+/// Short node names for the graph, since package ids are long and full of punctuation
+fn node_ids<'a>(labels: &BTreeMap<&'a str, String>) -> BTreeMap<&'a str, String> {
+    labels
+        .keys()
+        .enumerate()
+        .map(|(n, id)| (*id, format!("n{n}")))
+        .collect()
+}
+
+fn render_dot(
+    labels: &BTreeMap<&str, String>,
+    edges: &[GraphEdge],
+    is_member: impl Fn(&str) -> bool,
+) -> String {
+    let ids = node_ids(labels);
+
+    let mut dot = "digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n".to_string();
+    for (id, label) in labels {
+        let style = if is_member(id) { " style=bold" } else { "" };
+        let _ = writeln!(dot, "    {} [label={label:?}{style}];", ids[id]);
+    }
+    for edge in edges {
+        let style = edge
+            .kind
+            .map(|kind| format!(" [style=dashed label={kind:?}]"))
+            .unwrap_or_default();
+        let _ = writeln!(dot, "    {} -> {}{style};", ids[edge.from], ids[edge.to]);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn render_mermaid(
+    labels: &BTreeMap<&str, String>,
+    edges: &[GraphEdge],
+    is_member: impl Fn(&str) -> bool,
+) -> String {
+    let ids = node_ids(labels);
+
+    let mut mermaid = "graph LR\n".to_string();
+    for (id, label) in labels {
+        let _ = writeln!(
+            mermaid,
+            "    {}[\"{}\"]",
+            ids[id],
+            label.replace('"', "#quot;")
+        );
+    }
+    for edge in edges {
+        let arrow = edge
+            .kind
+            .map_or_else(|| "-->".to_string(), |kind| format!("-.->|{kind}|"));
+        let _ = writeln!(mermaid, "    {} {arrow} {}", ids[edge.from], ids[edge.to]);
+    }
+
+    let members: Vec<&str> = labels
+        .keys()
+        .filter(|id| is_member(id))
+        .map(|id| ids[id].as_str())
+        .collect();
+    if !members.is_empty() {
+        let _ = writeln!(
+            mermaid,
+            "    classDef member font-weight:bold,stroke-width:2px\n    class {} member",
+            members.join(",")
+        );
+    }
+    mermaid
+}
@@ -0,0 +1,223 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, first_error, run_cargo_command,
+};
+use crate::tools::feature_combinations::combinations;
+use crate::tools::metadata::load_metadata;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write, sync::atomic::Ordering};
+
+/// Combinations checked when no max_combinations is given
+const DEFAULT_MAX_COMBINATIONS: usize = 32;
+
+/// Check that the package compiles with each of its feature combinations
+///
+/// Reads the package's features with cargo metadata and runs cargo check with default
+/// features, with no default features, with all features, with each feature on its own,
+/// and optionally with every combination of up to powerset_depth features. Reports a
+/// table of the combinations with the first error of each one that fails.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_feature_matrix")]
+pub struct CargoFeatureMatrix {
+    /// Optional package name to check (for workspaces)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Also check every combination of up to this many features (2 or more)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub powerset_depth: Option<usize>,
+
+    /// Features to leave out of every combination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub exclude_features: Option<Vec<String>>,
+
+    /// Maximum number of combinations to check (defaults to 32)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub max_combinations: Option<usize>,
+
+    /// Check all targets (tests, examples, benches), not just the library and binaries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub all_targets: Option<bool>,
+
+    /// Check for the given target triple (e.g., 'wasm32-unknown-unknown')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+
+    /// Optional timeout in seconds for each check, after which it and everything it started
+    /// are killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
+    /// Optional environment variables to set for the cargo command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(skip)]
+    pub cargo_env: Option<HashMap<String, String>>,
+}
+
+impl WithExamples for CargoFeatureMatrix {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Check each feature on its own, with no and with all features",
+                item: Self {
+                    package: None,
+                    powerset_depth: None,
+                    exclude_features: None,
+                    max_combinations: None,
+                    all_targets: None,
+                    target: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Also check every pair of features, except a nightly-only one",
+                item: Self {
+                    package: Some("my-lib".into()),
+                    powerset_depth: Some(2),
+                    exclude_features: Some(vec!["nightly".into()]),
+                    max_combinations: Some(100),
+                    all_targets: None,
+                    target: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoFeatureMatrix {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), true, &limits)?;
+        let package = metadata.select_member(self.package.as_deref(), &project_path)?;
+        let excluded = self.exclude_features.unwrap_or_default();
+
+        let max_combinations = self
+            .max_combinations
+            .unwrap_or(DEFAULT_MAX_COMBINATIONS)
+            .max(1);
+        let (combinations, skipped) = combinations(
+            package.features.keys().map(String::as_str).collect(),
+            &excluded,
+            self.powerset_depth.unwrap_or(1),
+            max_combinations,
+        );
+
+        // Progress is reported per combination rather than per build
+        let progress = state.request().progress().cloned();
+        state.set_request(state.request().without_progress());
+
+        let mut rows = vec![];
+        let mut failed = 0;
+        for (index, combination) in combinations.iter().enumerate() {
+            if state.request().cancelled().load(Ordering::SeqCst) {
+                break;
+            }
+            if let Some(progress) = &progress {
+                progress.notify(
+                    index as u64,
+                    Some(combinations.len() as u64),
+                    &format!("Checking {}", combination.label),
+                );
+            }
+
+            let mut args = vec!["check", "--message-format=json", "--package", &package.name];
+            if self.all_targets.unwrap_or(false) {
+                args.push("--all-targets");
+            }
+            if let Some(ref target) = self.target {
+                args.extend_from_slice(&["--target", target]);
+            }
+            args.extend(combination.args.iter().map(String::as_str));
+
+            let mut cmd =
+                create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+            let output = run_cargo_command(
+                &mut cmd,
+                &project_path,
+                "cargo check",
+                self.timeout_secs,
+                state,
+            )?;
+
+            let outcome = if output.success() {
+                "✅".to_string()
+            } else {
+                failed += 1;
+                match output.run_id {
+                    Some(run_id) => format!("❌ run {run_id}"),
+                    None => "❌".to_string(),
+                }
+            };
+            let error = if output.success() {
+                String::new()
+            } else {
+                first_error(&output).replace('|', "\\|")
+            };
+            rows.push((combination.label.as_str(), outcome, error));
+        }
+
+        let mut result = format!("=== cargo feature matrix: {} ===\n", package.name);
+        let _ = writeln!(result, "📁 Working directory: {}\n", project_path.display());
+        let _ = writeln!(
+            result,
+            "🧮 {} feature combinations checked: {} passed, {failed} failed",
+            rows.len(),
+            rows.len() - failed
+        );
+        if rows.len() < combinations.len() {
+            let _ = writeln!(
+                result,
+                "🛑 Cancelled; {} combinations were not checked",
+                combinations.len() - rows.len()
+            );
+        }
+        if skipped > 0 {
+            let _ = writeln!(
+                result,
+                "⚠️  {skipped} more combinations were skipped; raise max_combinations to check them"
+            );
+        }
+
+        result.push_str("\n| Features | Result | First error |\n|---|---|---|\n");
+        for (label, outcome, error) in &rows {
+            let _ = writeln!(result, "| {label} | {outcome} | {error} |");
+        }
+
+        if failed > 0 {
+            result.push_str(
+                "\nUse cargo_log with a failing combination's run id to see its full output.\n",
+            );
+        }
+
+        Ok(result)
+    }
+}
@@ -17,6 +17,12 @@ pub struct CargoFmtCheck {
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -30,6 +36,7 @@ impl WithExamples for CargoFmtCheck {
                 description: "Check formatting in current project",
                 item: Self {
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -37,6 +44,7 @@ impl WithExamples for CargoFmtCheck {
                 description: "Check formatting with nightly toolchain",
                 item: Self {
                     toolchain: Some("nightly".into()),
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -47,14 +55,20 @@ impl WithExamples for CargoFmtCheck {
 impl Tool<CargoTools> for CargoFmtCheck {
     fn execute(self, state: &mut CargoTools) -> Result<String> {
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
 
-
         let args = vec!["fmt", "--check"];
         let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo fmt --check")
+        execute_cargo_command(
+            cmd,
+            &project_path,
+            "cargo fmt --check",
+            self.timeout_secs,
+            state,
+        )
     }
 }
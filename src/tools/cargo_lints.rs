@@ -0,0 +1,321 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::command_limits;
+use crate::tools::lints::{LintLevel, LintSetting, lint_settings, set_lint};
+use crate::tools::manifest::{Manifest, save_checked};
+use crate::tools::metadata::load_metadata;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use toml_edit::Item;
+
+/// List, set or remove lint levels in Cargo.toml's [lints] tables
+///
+/// Without a lint, lists the levels in [workspace.lints] and each member's [lints]. With a
+/// lint such as 'unsafe_code', 'clippy::pedantic' or 'rustdoc::broken_intra_doc_links',
+/// sets its level or removes it, in a package's [lints] or, with workspace, in
+/// [workspace.lints]. Lint groups are given a lower priority so individual lints can
+/// override them. With inherit, members opt into the workspace's lints with
+/// `lints.workspace = true`. Edits are checked with cargo metadata before they are written,
+/// and dropped if cargo rejects them. Lint tables need Rust 1.74 or later.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_lints")]
+pub struct CargoLints {
+    /// Lint or lint group to set or remove, e.g. 'unsafe_code' or 'clippy::pedantic'.
+    /// Leave out to list the current lint levels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub lint: Option<String>,
+
+    /// Level to set the lint to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, value_enum)]
+    pub level: Option<LintLevel>,
+
+    /// Remove the lint's level instead of setting it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub remove: Option<bool>,
+
+    /// Edit [workspace.lints] in the workspace root instead of a package's [lints]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub workspace: Option<bool>,
+
+    /// Make the package, or every workspace member if no package is given, use the
+    /// workspace's lints (`lints.workspace = true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub inherit: Option<bool>,
+
+    /// Optional package whose [lints] to edit (for workspaces)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Check the edit and show the diff without keeping it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub dry_run: Option<bool>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoLints {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "List the lint levels set in the workspace",
+                item: Self {
+                    lint: None,
+                    level: None,
+                    remove: None,
+                    workspace: None,
+                    inherit: None,
+                    package: None,
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Forbid unsafe code in a package",
+                item: Self {
+                    lint: Some("unsafe_code".into()),
+                    level: Some(LintLevel::Forbid),
+                    remove: None,
+                    workspace: None,
+                    inherit: None,
+                    package: Some("my-lib".into()),
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Warn about pedantic clippy lints across the workspace",
+                item: Self {
+                    lint: Some("clippy::pedantic".into()),
+                    level: Some(LintLevel::Warn),
+                    remove: None,
+                    workspace: Some(true),
+                    inherit: Some(true),
+                    package: None,
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Stop allowing a lint",
+                item: Self {
+                    lint: Some("clippy::too_many_lines".into()),
+                    level: None,
+                    remove: Some(true),
+                    workspace: Some(true),
+                    inherit: None,
+                    package: None,
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoLints {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), true, &limits)?;
+        let root_path = metadata.workspace_root.join("Cargo.toml");
+        let members: Vec<(String, PathBuf)> = metadata
+            .members()
+            .map(|package| (package.name.clone(), package.manifest_path.clone()))
+            .collect();
+
+        // The root manifest first, then each member's, loaded once even if the root is a
+        // package too
+        let mut manifests: Vec<(PathBuf, Manifest)> = vec![];
+        for path in std::iter::once(&root_path).chain(members.iter().map(|(_, path)| path)) {
+            if !manifests.iter().any(|(loaded, _)| loaded == path) {
+                manifests.push((path.clone(), Manifest::load(path)?));
+            }
+        }
+        let index = |manifests: &[(PathBuf, Manifest)], path: &Path| {
+            manifests
+                .iter()
+                .position(|(loaded, _)| loaded == path)
+                .unwrap_or_default()
+        };
+
+        let workspace = self.workspace.unwrap_or(false);
+        let remove = self.remove.unwrap_or(false);
+        if let Some(lint) = &self.lint {
+            let level = match (self.level, remove) {
+                (Some(_), true) => return Err(anyhow!("Pass either level or remove, not both")),
+                (None, false) => return Err(anyhow!("Pass a level for {lint}, or remove")),
+                (level, _) => level,
+            };
+
+            if workspace {
+                set_lint(manifests[0].1.workspace_lints()?, lint, level)?;
+            } else {
+                let package = metadata.select_member(self.package.as_deref(), &project_path)?;
+                let position = index(&manifests, &package.manifest_path);
+                let (_, manifest) = &mut manifests[position];
+                if inherits_workspace(manifest) {
+                    return Err(anyhow!(
+                        "{} uses the workspace's lints (lints.workspace = true); pass workspace \
+                         to edit [workspace.lints] instead",
+                        package.name
+                    ));
+                }
+                set_lint(manifest.lints()?, lint, level)?;
+            }
+        } else if self.level.is_some() || remove {
+            return Err(anyhow!("Pass the lint to set or remove"));
+        }
+
+        if self.inherit.unwrap_or(false) {
+            let packages: Vec<&(String, PathBuf)> = match self.package.as_deref() {
+                Some(name) => {
+                    metadata.member(name)?;
+                    members
+                        .iter()
+                        .filter(|(member, _)| member == name)
+                        .collect()
+                }
+                None => members.iter().collect(),
+            };
+            for (name, path) in packages {
+                let position = index(&manifests, path);
+                let (_, manifest) = &mut manifests[position];
+                let lints = manifest.lints()?;
+                let own: Vec<String> = lint_settings(name, &*lints)
+                    .into_iter()
+                    .map(|setting| setting.lint)
+                    .collect();
+                if !own.is_empty() {
+                    return Err(anyhow!(
+                        "{name} sets its own lint levels ({}), which cargo doesn't allow \
+                         alongside lints.workspace = true; move them to [workspace.lints] or \
+                         remove them first",
+                        own.join(", ")
+                    ));
+                }
+                lints.insert("workspace", toml_edit::value(true));
+            }
+        }
+
+        let mut result = "=== cargo lints ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}\n", project_path.display());
+
+        let edited: Vec<(&Path, &Manifest)> = manifests
+            .iter()
+            .filter(|(_, manifest)| manifest.is_changed())
+            .map(|(path, manifest)| (path.as_path(), manifest))
+            .collect();
+        let relative = |path: &Path| {
+            path.strip_prefix(&metadata.workspace_root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let diff: String = edited
+            .iter()
+            .map(|(path, manifest)| manifest.diff(&relative(path)))
+            .collect();
+
+        if self.lint.is_some() || self.inherit.is_some() {
+            if edited.is_empty() {
+                result.push_str("ℹ️  The lint levels are already set this way\n\n");
+            } else {
+                let dry_run = self.dry_run.unwrap_or(false);
+                let warnings =
+                    save_checked(&edited, &metadata, toolchain.as_deref(), dry_run, &limits)
+                        .map_err(|e| anyhow!("{e}\n\nThe rejected edit was:\n{diff}"))?;
+                let _ = writeln!(
+                    result,
+                    "{}:\n{diff}",
+                    if dry_run {
+                        "🔍 Would edit"
+                    } else {
+                        "✅ Edited"
+                    }
+                );
+                if !warnings.is_empty() {
+                    let _ = writeln!(result, "⚠️  cargo warnings:\n{}\n", warnings.join("\n"));
+                }
+            }
+        }
+
+        // The lint levels as edited, so a dry run shows what they would be
+        let root = &manifests[0].1.document;
+        if let Some(lints) = root
+            .get("workspace")
+            .and_then(|workspace| workspace.get("lints"))
+            .and_then(Item::as_table_like)
+        {
+            let workspace_settings = lint_settings("workspace", lints);
+            result.push_str("🧹 [workspace.lints]:\n");
+            render_settings(&mut result, &workspace_settings);
+        }
+        for (name, path) in &members {
+            let manifest = &manifests[index(&manifests, path)].1;
+            if inherits_workspace(manifest) {
+                let _ = writeln!(result, "📦 {name}: uses [workspace.lints]");
+                continue;
+            }
+            let own = manifest
+                .document
+                .get("lints")
+                .and_then(Item::as_table_like)
+                .map(|lints| lint_settings(name, lints))
+                .unwrap_or_default();
+            if own.is_empty() {
+                let _ = writeln!(result, "📦 {name}: no lint levels set");
+            } else {
+                let _ = writeln!(result, "📦 {name}:");
+                render_settings(&mut result, &own);
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Whether the manifest has `lints.workspace = true`
+fn inherits_workspace(manifest: &Manifest) -> bool {
+    manifest
+        .document
+        .get("lints")
+        .and_then(|lints| lints.get("workspace"))
+        .and_then(Item::as_bool)
+        .unwrap_or(false)
+}
+
+fn render_settings(result: &mut String, settings: &[LintSetting]) {
+    if settings.is_empty() {
+        result.push_str("  no lint levels set\n");
+    }
+    for setting in settings {
+        let priority = setting
+            .priority
+            .map(|priority| format!(" (priority {priority})"))
+            .unwrap_or_default();
+        let _ = writeln!(result, "  {} = {}{priority}", setting.lint, setting.level);
+    }
+}
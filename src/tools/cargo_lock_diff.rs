@@ -0,0 +1,177 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{capture_output, command_limits, workspace_root};
+use crate::tools::diagnostics::plural;
+use crate::tools::lockfile::{
+    ChangeKind, diff_lockfiles, duplicates, parse_lockfile, render_lock_changes,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, fs, io::ErrorKind, process::Command};
+
+/// Compare Cargo.lock with the version committed at a git revision
+///
+/// Reports the packages added, removed, upgraded and downgraded since the revision,
+/// flagging semver-incompatible version changes and packages that are now locked at more
+/// than one version.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_lock_diff")]
+pub struct CargoLockDiff {
+    /// Git revision to compare against, e.g. 'main' or 'HEAD~3' (defaults to HEAD)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub git_ref: Option<String>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoLockDiff {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Show uncommitted changes to Cargo.lock",
+                item: Self {
+                    git_ref: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Review the dependency changes on a branch",
+                item: Self {
+                    git_ref: Some("main".into()),
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoLockDiff {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let root = workspace_root(&project_path, toolchain.as_deref(), &limits)?;
+        let git_ref = self.git_ref.as_deref().unwrap_or("HEAD");
+        // git would take it for an option
+        if git_ref.starts_with('-') {
+            return Err(anyhow!("Invalid git_ref '{git_ref}'"));
+        }
+        let lockfile = root.join("Cargo.lock");
+        let current = match fs::read_to_string(&lockfile) {
+            Ok(current) => current,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(anyhow!(
+                    "The working tree has no Cargo.lock to compare; build the project or run \
+                     cargo_update to create one"
+                ));
+            }
+            Err(e) => return Err(anyhow!("Could not read {}: {e}", lockfile.display())),
+        };
+
+        let git = |args: &[&str]| {
+            let mut cmd = Command::new("git");
+            cmd.args(args);
+            capture_output(&mut cmd, &root, &limits).map_err(|e| {
+                anyhow!("Comparing against git needs git, which could not be run: {e}")
+            })
+        };
+
+        // Resolve the ref first, so that a Cargo.lock missing at it can be told apart from a
+        // ref that doesn't exist without reading git's (possibly translated) messages
+        let commit = git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{git_ref}^{{commit}}"),
+        ])?;
+        if !commit.success() {
+            return Err(anyhow!(
+                "Could not resolve {git_ref} to a commit{}",
+                match commit.stderr.trim() {
+                    "" => String::new(),
+                    stderr => format!(": {stderr}"),
+                }
+            ));
+        }
+
+        let mut result = format!("=== cargo lock diff: {git_ref} → working tree ===\n");
+        let _ = writeln!(result, "📁 Working directory: {}\n", root.display());
+
+        let path = format!("{git_ref}:./Cargo.lock");
+        let previous = if git(&["cat-file", "-e", &path])?.success() {
+            let output = git(&["show", &path])?;
+            if !output.success() {
+                return Err(anyhow!(
+                    "Could not read Cargo.lock at {git_ref}: {}",
+                    output.stderr.trim()
+                ));
+            }
+            output.stdout
+        } else {
+            let _ = writeln!(result, "ℹ️  Cargo.lock did not exist at {git_ref}\n");
+            String::new()
+        };
+
+        let changes = diff_lockfiles(&previous, &current);
+        let count = |kind: ChangeKind| changes.iter().filter(|c| c.change == kind).count();
+        let majors: Vec<_> = changes.iter().filter(|c| c.semver_major).collect();
+        let _ = writeln!(
+            result,
+            "🧮 {} added, {} removed, {} upgraded, {} downgraded",
+            count(ChangeKind::Added),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Upgraded),
+            count(ChangeKind::Downgraded)
+        );
+        if !majors.is_empty() {
+            let _ = writeln!(
+                result,
+                "⚠️  {}: {}",
+                plural(majors.len(), "semver-major change", "semver-major changes"),
+                majors
+                    .iter()
+                    .map(|change| change.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        let before = parse_lockfile(&previous);
+        let after = parse_lockfile(&current);
+        let new_duplicates: Vec<_> = duplicates(&after)
+            .into_iter()
+            .filter(|(name, versions)| {
+                before
+                    .get(*name)
+                    .is_none_or(|old| old.len() < versions.len())
+            })
+            .collect();
+        if !new_duplicates.is_empty() {
+            let _ = writeln!(
+                result,
+                "🔁 {} now locked at more than one version:",
+                plural(new_duplicates.len(), "package is", "packages are")
+            );
+            for (name, versions) in &new_duplicates {
+                let _ = writeln!(result, "  {name}: {}", versions.join(", "));
+            }
+            result.push_str("Use cargo_tree with duplicates to see what requires each version.\n");
+        }
+        result.push('\n');
+
+        result.push_str(&render_lock_changes("Cargo.lock changes", &changes));
+        Ok(result)
+    }
+}
@@ -0,0 +1,303 @@
+use crate::command_log::{LogStream, RunRecord};
+use crate::state::CargoTools;
+use crate::tools::output::strip_ansi;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Number of lines (or matches) returned when no line_count is given
+const DEFAULT_LINE_COUNT: usize = 200;
+
+/// Number of runs shown when listing
+const LIST_LIMIT: usize = 20;
+
+/// Read the full output of an earlier cargo command
+///
+/// Every cargo command run by this server has its complete stdout and stderr logged under
+/// a run id, which is shown in each response. Use this to list recent runs, page through a
+/// line range of a run's output, or search it with a regular expression.
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_log")]
+pub struct CargoLog {
+    /// List recent runs instead of reading output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub list: Option<bool>,
+
+    /// Run to read (defaults to the most recent run)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub run_id: Option<u64>,
+
+    /// Output stream to read. When reading, defaults to stdout (or stderr if stdout is
+    /// empty); when searching, both streams are searched unless one is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, value_enum)]
+    pub stream: Option<LogStream>,
+
+    /// First line to return, starting at 1 (defaults to 1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub start_line: Option<usize>,
+
+    /// Maximum number of lines to return, or of matches when searching (defaults to 200)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub line_count: Option<usize>,
+
+    /// Regular expression to search the output for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub pattern: Option<String>,
+
+    /// Lines of context to show around each match (defaults to 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub context: Option<usize>,
+}
+
+impl WithExamples for CargoLog {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "List recent runs",
+                item: Self {
+                    list: Some(true),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Read the first 200 lines of the most recent run",
+                item: Self::default(),
+            },
+            Example {
+                description: "Read lines 400-499 of stderr from run 12",
+                item: Self {
+                    run_id: Some(12),
+                    stream: Some(LogStream::Stderr),
+                    start_line: Some(400),
+                    line_count: Some(100),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Search run 12 for errors, with two lines of context",
+                item: Self {
+                    run_id: Some(12),
+                    pattern: Some("^error".into()),
+                    context: Some(2),
+                    ..Self::default()
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoLog {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let session_id = state.default_session_id();
+        let log = state.command_log();
+
+        if self.list.unwrap_or(false) {
+            return Ok(render_list(&log.list(session_id, LIST_LIMIT)));
+        }
+
+        let record = log.get(session_id, self.run_id)?;
+        let line_count = self.line_count.unwrap_or(DEFAULT_LINE_COUNT).max(1);
+        let mut result = render_record(&record);
+
+        if let Some(pattern) = &self.pattern {
+            let regex =
+                Regex::new(pattern).map_err(|e| anyhow!("Invalid pattern {pattern:?}: {e}"))?;
+            let streams = match self.stream {
+                Some(stream) => vec![stream],
+                None => vec![LogStream::Stdout, LogStream::Stderr],
+            };
+
+            let mut remaining = line_count;
+            for stream in streams {
+                let text = strip_ansi(&log.read(session_id, record.id, stream)?);
+                remaining -= search(
+                    &mut result,
+                    stream,
+                    &text,
+                    &regex,
+                    self.context.unwrap_or(0),
+                    remaining,
+                );
+                if remaining == 0 {
+                    let _ = writeln!(
+                        result,
+                        "ℹ️  Stopped after {line_count} matches; raise line_count to see more"
+                    );
+                    break;
+                }
+            }
+            if remaining == line_count {
+                let _ = writeln!(result, "ℹ️  No lines match {pattern:?}");
+            }
+            return Ok(result);
+        }
+
+        let stream = self
+            .stream
+            .unwrap_or(if record.stdout_lines == 0 && !record.running {
+                LogStream::Stderr
+            } else {
+                LogStream::Stdout
+            });
+        let text = strip_ansi(&log.read(session_id, record.id, stream)?);
+        let lines: Vec<&str> = text.lines().collect();
+        let start = self.start_line.unwrap_or(1).max(1);
+        let end = (start - 1 + line_count).min(lines.len());
+
+        if start > lines.len() {
+            let _ = writeln!(
+                result,
+                "ℹ️  {} has {} lines; start_line {start} is past the end",
+                stream.name(),
+                lines.len()
+            );
+            return Ok(result);
+        }
+
+        let _ = writeln!(
+            result,
+            "📤 {} lines {start}-{end} of {}:",
+            stream.name(),
+            lines.len()
+        );
+        for (index, line) in lines.iter().enumerate().take(end).skip(start - 1) {
+            let _ = writeln!(result, "{:>6}  {line}", index + 1);
+        }
+        if end < lines.len() {
+            let _ = writeln!(
+                result,
+                "\nℹ️  {} more lines; continue with start_line={}",
+                lines.len() - end,
+                end + 1
+            );
+        }
+
+        Ok(result)
+    }
+}
+
+/// Header describing a run
+fn render_record(record: &RunRecord) -> String {
+    if record.running {
+        return format!(
+            "=== cargo_log: run {} ({}) ===\n📁 Working directory: {}\n🔧 Command: {}\n🔄 still running, started {}; its output so far:\n\n",
+            record.id,
+            record.command_name,
+            record.project_path.display(),
+            record.command,
+            record.age(),
+        );
+    }
+
+    let status = match (record.interruption, record.exit_code) {
+        (Some(interruption), _) => format!("{}  {interruption}", interruption.icon()),
+        (None, Some(0)) => "✅ succeeded".to_string(),
+        (None, Some(code)) => format!("❌ failed with exit code {code}"),
+        (None, None) => "❌ terminated by a signal".to_string(),
+    };
+    format!(
+        "=== cargo_log: run {} ({}) ===\n📁 Working directory: {}\n🔧 Command: {}\n{status} in {:.1}s, {}; {} stdout lines, {} stderr lines\n\n",
+        record.id,
+        record.command_name,
+        record.project_path.display(),
+        record.command,
+        record.duration_ms as f64 / 1000.0,
+        record.age(),
+        record.stdout_lines,
+        record.stderr_lines,
+    )
+}
+
+fn render_list(records: &[RunRecord]) -> String {
+    if records.is_empty() {
+        return "ℹ️  No cargo commands have been logged yet\n".into();
+    }
+
+    let mut result = format!("=== cargo_log: {} most recent runs ===\n", records.len());
+    for record in records {
+        if record.running {
+            let _ = writeln!(
+                result,
+                "{:>4}  🔄  {} (running, started {})  {}",
+                record.id,
+                record.command_name,
+                record.age(),
+                record.project_path.display(),
+            );
+            continue;
+        }
+        let status = match (record.interruption, record.exit_code) {
+            (Some(interruption), _) => format!("{}  {interruption}", interruption.icon()),
+            (None, Some(0)) => "✅".to_string(),
+            (None, Some(code)) => format!("❌ {code}"),
+            (None, None) => "❌ signal".to_string(),
+        };
+        let _ = writeln!(
+            result,
+            "{:>4}  {status}  {} ({:.1}s, {}, {}/{} lines out/err)  {}",
+            record.id,
+            record.command_name,
+            record.duration_ms as f64 / 1000.0,
+            record.age(),
+            record.stdout_lines,
+            record.stderr_lines,
+            record.project_path.display(),
+        );
+    }
+    result
+}
+
+/// Append lines of `text` matching `regex`, with context, stopping after `limit` matches.
+/// Returns the number of matches written.
+fn search(
+    result: &mut String,
+    stream: LogStream,
+    text: &str,
+    regex: &Regex,
+    context: usize,
+    limit: usize,
+) -> usize {
+    let lines: Vec<&str> = text.lines().collect();
+    let matches: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(index, _)| index)
+        .take(limit)
+        .collect();
+
+    if matches.is_empty() {
+        return 0;
+    }
+
+    let _ = writeln!(result, "🔍 {} matches in {}:", matches.len(), stream.name());
+    // Index of the line after the last one written, to avoid repeating overlapping context
+    let mut written_to = 0;
+    for &index in &matches {
+        let start = index.saturating_sub(context).max(written_to);
+        let end = (index + context + 1).min(lines.len());
+        if context > 0 && written_to > 0 && start > written_to {
+            result.push_str("    --\n");
+        }
+        for (line_index, line) in lines.iter().enumerate().take(end).skip(start) {
+            let marker = if line_index == index { ':' } else { '-' };
+            let _ = writeln!(result, "{:>6}{marker} {line}", line_index + 1);
+        }
+        written_to = end;
+    }
+    result.push('\n');
+
+    matches.len()
+}
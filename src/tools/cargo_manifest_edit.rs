@@ -0,0 +1,164 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::command_limits;
+use crate::tools::manifest::{Manifest, save_checked};
+use crate::tools::metadata::load_metadata;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::PathBuf};
+
+/// Edit Cargo.toml while keeping its formatting and comments
+///
+/// Sets or removes one value in [features], [profile.*], [package] (including
+/// [package.metadata.*]), or a [[bin]] or [[example]] target, addressed by a dotted key.
+/// The edited manifest is checked with cargo metadata before it is written.
+/// Returns a diff of the change.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_manifest_edit")]
+pub struct CargoManifestEdit {
+    /// Dotted key to edit, e.g. 'features.serde', 'profile.release.lto', 'package.description',
+    /// 'package.metadata.docs.rs.all-features', 'bin.my-tool' or 'bin.my-tool.path'
+    pub key: String,
+
+    /// New value as TOML, e.g. 'true', '"fat"', '["dep:serde"]' or '{ path = "src/x.rs" }';
+    /// text that isn't valid TOML is used as a string. Leave out to remove the key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub value: Option<String>,
+
+    /// Optional package whose manifest to edit (for workspaces). Profiles are always
+    /// edited in the workspace root's manifest, since cargo ignores them elsewhere.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Check the edit and show the diff without keeping it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub dry_run: Option<bool>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoManifestEdit {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Add a feature that enables an optional dependency",
+                item: Self {
+                    key: "features.json".into(),
+                    value: Some(r#"["dep:serde_json"]"#.into()),
+                    package: None,
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Enable fat LTO for release builds",
+                item: Self {
+                    key: "profile.release.lto".into(),
+                    value: Some(r#""fat""#.into()),
+                    package: None,
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Add a binary target",
+                item: Self {
+                    key: "bin.my-tool".into(),
+                    value: Some(
+                        r#"{ path = "src/bin/my_tool.rs", required-features = ["cli"] }"#.into(),
+                    ),
+                    package: Some("my-lib".into()),
+                    dry_run: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Remove the package description",
+                item: Self {
+                    key: "package.description".into(),
+                    value: None,
+                    package: None,
+                    dry_run: Some(true),
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoManifestEdit {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), true, &limits)?;
+        let manifest_path: PathBuf = if self.key.starts_with("profile.") {
+            metadata.workspace_root.join("Cargo.toml")
+        } else {
+            match self.package.as_deref() {
+                Some(name) => metadata.member(name)?.manifest_path.clone(),
+                None => project_path.join("Cargo.toml"),
+            }
+        };
+        let relative = manifest_path
+            .strip_prefix(&metadata.workspace_root)
+            .unwrap_or(&manifest_path)
+            .display()
+            .to_string();
+
+        let mut manifest = Manifest::load(&manifest_path)?;
+        manifest.edit(&self.key, self.value.as_deref())?;
+
+        let mut result = "=== cargo manifest edit ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}\n", project_path.display());
+        if !manifest.is_changed() {
+            let _ = writeln!(result, "ℹ️  {relative} already has this value");
+            return Ok(result);
+        }
+
+        let dry_run = self.dry_run.unwrap_or(false);
+        let warnings = save_checked(
+            &[(&manifest_path, &manifest)],
+            &metadata,
+            toolchain.as_deref(),
+            dry_run,
+            &limits,
+        )
+        .map_err(|e| {
+            anyhow!(
+                "{e}\n\nThe rejected edit to {relative} was:\n{}",
+                manifest.diff(&relative)
+            )
+        })?;
+
+        let _ = writeln!(
+            result,
+            "{} {relative}:\n{}",
+            if dry_run {
+                "🔍 Would edit"
+            } else {
+                "✅ Edited"
+            },
+            manifest.diff(&relative)
+        );
+        if !warnings.is_empty() {
+            let _ = writeln!(result, "⚠️  cargo warnings:\n{}", warnings.join("\n"));
+        }
+
+        Ok(result)
+    }
+}
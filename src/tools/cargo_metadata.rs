@@ -0,0 +1,202 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::command_limits;
+use crate::tools::metadata::{Metadata, Package, load_metadata};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::Path};
+
+/// Summarize the workspace's layout using cargo metadata
+///
+/// Reports the workspace root and target directory, and for each member its targets
+/// (with any required features), its features and what they enable, and its dependencies
+/// with the versions they resolved to.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_metadata")]
+pub struct CargoMetadata {
+    /// Optional package name to describe (defaults to every workspace member)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Skip dependency resolution; faster, but resolved versions are not shown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub no_deps: Option<bool>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoMetadata {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Summarize the whole workspace",
+                item: Self {
+                    package: None,
+                    no_deps: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Quickly list one member's targets and features",
+                item: Self {
+                    package: Some("my-lib".into()),
+                    no_deps: Some(true),
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoMetadata {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let no_deps = self.no_deps.unwrap_or(false);
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), no_deps, &limits)?;
+        let members: Vec<&Package> = match self.package.as_deref() {
+            Some(name) => vec![metadata.select_member(Some(name), &project_path)?],
+            None => metadata.members().collect(),
+        };
+
+        let mut result = "=== cargo metadata ===\n".to_string();
+        let _ = writeln!(
+            result,
+            "📁 Workspace root: {}",
+            metadata.workspace_root.display()
+        );
+        let _ = writeln!(
+            result,
+            "🎯 Target directory: {}",
+            metadata.target_directory.display()
+        );
+        let _ = writeln!(
+            result,
+            "📦 Members: {}",
+            metadata
+                .members()
+                .map(|package| package.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        for package in members {
+            render_package(&mut result, &metadata, package, no_deps);
+        }
+
+        Ok(result)
+    }
+}
+
+fn render_package(result: &mut String, metadata: &Metadata, package: &Package, no_deps: bool) {
+    let package_dir = package.manifest_path.parent().unwrap_or(Path::new(""));
+    let _ = writeln!(
+        result,
+        "\n── {} v{} ({}) ──",
+        package.name,
+        package.version,
+        relative(&package.manifest_path, &metadata.workspace_root)
+    );
+    if let Some(ref rust_version) = package.rust_version {
+        let _ = writeln!(result, "rust-version: {rust_version}");
+    }
+
+    result.push_str("Targets:\n");
+    for target in package
+        .targets
+        .iter()
+        .filter(|target| !target.is("custom-build"))
+    {
+        let kind = ["lib", "bin", "example", "test", "bench"]
+            .into_iter()
+            .find(|kind| target.is(kind))
+            .unwrap_or_else(|| target.kind.first().map_or("?", String::as_str));
+        let _ = write!(
+            result,
+            "  {kind} {} ({})",
+            target.name,
+            relative(&target.src_path, package_dir)
+        );
+        if !target.required_features.is_empty() {
+            let _ = write!(
+                result,
+                " requires features: {}",
+                target.required_features.join(", ")
+            );
+        }
+        result.push('\n');
+    }
+
+    if !package.features.is_empty() {
+        result.push_str("Features:\n");
+        for (feature, enables) in &package.features {
+            let _ = writeln!(result, "  {feature} = [{}]", enables.join(", "));
+        }
+    }
+
+    if package.dependencies.is_empty() {
+        return;
+    }
+    result.push_str("Dependencies:\n");
+    let resolved = metadata.resolved_deps(package);
+    for dependency in &package.dependencies {
+        result.push_str("  ");
+        if let Some(ref kind) = dependency.kind {
+            let _ = write!(result, "[{kind}] ");
+        }
+        result.push_str(&dependency.name);
+        if let Some(ref rename) = dependency.rename {
+            let _ = write!(result, " as {rename}");
+        }
+        let _ = write!(result, " {}", dependency.req);
+
+        if !no_deps {
+            let versions: Vec<&str> = resolved
+                .iter()
+                .filter(|dep| dep.name == dependency.name)
+                .map(|dep| dep.version.as_str())
+                .collect();
+            match versions.as_slice() {
+                // Optional dependencies whose feature is off aren't resolved
+                [] => result.push_str(" → (not resolved)"),
+                versions => {
+                    let _ = write!(result, " → {}", versions.join(", "));
+                }
+            }
+        }
+
+        let mut notes = vec![];
+        if dependency.optional {
+            notes.push("optional".to_string());
+        }
+        if let Some(ref target) = dependency.target {
+            notes.push(format!("only on {target}"));
+        }
+        if !notes.is_empty() {
+            let _ = write!(result, " ({})", notes.join(", "));
+        }
+        result.push('\n');
+    }
+}
+
+/// A path relative to `base` if it is inside it, for shorter output
+fn relative(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,199 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, first_error, installed_toolchains, matching_toolchain,
+    run_cargo_command,
+};
+use crate::tools::diagnostics::plural;
+use crate::tools::metadata::load_metadata;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write, sync::atomic::Ordering};
+
+/// Check that workspace members compile with their declared minimum supported Rust version
+///
+/// Reads `rust-version` from each member's manifest and runs cargo check with that
+/// toolchain. Members that don't declare a rust-version are skipped, and toolchains that
+/// aren't installed are reported rather than installed.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_msrv_check")]
+pub struct CargoMsrvCheck {
+    /// Optional package name to check (defaults to every workspace member)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Optional timeout in seconds for each check, after which it and everything it started
+    /// are killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
+    /// Optional environment variables to set for the cargo command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(skip)]
+    pub cargo_env: Option<HashMap<String, String>>,
+}
+
+impl WithExamples for CargoMsrvCheck {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Check every workspace member on its MSRV",
+                item: Self {
+                    package: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Check one package on its MSRV",
+                item: Self {
+                    package: Some("my-lib".into()),
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoMsrvCheck {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+        let default_toolchain = state.get_default_toolchain(None)?;
+        let limits = command_limits(None, state)?;
+        let metadata = load_metadata(&project_path, default_toolchain.as_deref(), true, &limits)?;
+
+        let members: Vec<_> = match self.package.as_deref() {
+            Some(name) => vec![metadata.select_member(Some(name), &project_path)?],
+            None => metadata.members().collect(),
+        };
+        let installed = installed_toolchains(&project_path, &limits)?;
+
+        // Progress is reported per member rather than per build
+        let progress = state.request().progress().cloned();
+        state.set_request(state.request().without_progress());
+
+        let mut rows = vec![];
+        let mut failed = 0;
+        let mut missing = vec![];
+        for (index, package) in members.iter().enumerate() {
+            if state.request().cancelled().load(Ordering::SeqCst) {
+                break;
+            }
+
+            let Some(ref msrv) = package.rust_version else {
+                rows.push((
+                    package.name.as_str(),
+                    "-",
+                    "➖".into(),
+                    "no rust-version declared".into(),
+                ));
+                continue;
+            };
+            let Some(toolchain) = matching_toolchain(&installed, msrv) else {
+                missing.push(msrv.as_str());
+                rows.push((
+                    package.name.as_str(),
+                    msrv.as_str(),
+                    "⚠️".into(),
+                    format!("toolchain {msrv} is not installed"),
+                ));
+                continue;
+            };
+
+            if let Some(progress) = &progress {
+                progress.notify(
+                    index as u64,
+                    Some(members.len() as u64),
+                    &format!("Checking {} with {toolchain}", package.name),
+                );
+            }
+
+            let args = ["check", "--message-format=json", "--package", &package.name];
+            let mut cmd = create_cargo_command(&args, Some(toolchain), self.cargo_env.as_ref());
+            let output = run_cargo_command(
+                &mut cmd,
+                &project_path,
+                "cargo check",
+                self.timeout_secs,
+                state,
+            )?;
+
+            if output.success() {
+                rows.push((
+                    package.name.as_str(),
+                    msrv.as_str(),
+                    "✅".into(),
+                    format!("compiles with {toolchain}"),
+                ));
+            } else {
+                failed += 1;
+                let outcome = match output.run_id {
+                    Some(run_id) => format!("❌ run {run_id}"),
+                    None => "❌".into(),
+                };
+                rows.push((
+                    package.name.as_str(),
+                    msrv.as_str(),
+                    outcome,
+                    first_error(&output).replace('|', "\\|"),
+                ));
+            }
+        }
+
+        let mut result = "=== cargo msrv check ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}\n", project_path.display());
+        if rows.len() < members.len() {
+            let _ = writeln!(
+                result,
+                "🛑 Cancelled; {} not checked\n",
+                plural(members.len() - rows.len(), "member was", "members were")
+            );
+        }
+        if failed > 0 {
+            let _ = writeln!(
+                result,
+                "❌ {} not compile on their MSRV",
+                plural(failed, "member does", "members do")
+            );
+        }
+        if !missing.is_empty() {
+            let _ = writeln!(
+                result,
+                "⚠️  {} not be checked because their toolchain is not installed",
+                plural(missing.len(), "member could", "members could")
+            );
+        }
+        if failed == 0 && missing.is_empty() {
+            result.push_str("✅ Every member with a rust-version compiles on it\n");
+        }
+
+        result.push_str("\n| Package | rust-version | Result | Details |\n|---|---|---|---|\n");
+        for (name, msrv, outcome, details) in &rows {
+            let _ = writeln!(result, "| {name} | {msrv} | {outcome} | {details} |");
+        }
+
+        if !missing.is_empty() {
+            missing.sort_unstable();
+            missing.dedup();
+            let _ = writeln!(
+                result,
+                "\n⚠️  Install the missing toolchains to check those members: rustup toolchain \
+                 install {}",
+                missing.join(" ")
+            );
+        }
+        if failed > 0 {
+            result.push_str(
+                "\nUse cargo_log with a failing member's run id to see its full output.\n",
+            );
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,340 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, render_command_output, resolve_inside, run_cargo_command,
+};
+use crate::tools::manifest::{Manifest, save_checked};
+use crate::tools::metadata::load_metadata;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{InlineTable, Item, Value};
+
+/// Create a new package with cargo new, or in an existing directory with cargo init
+///
+/// The package must be inside the working directory. When it is inside a workspace, it is
+/// added to [workspace] members if cargo didn't already, and with inherit its manifest
+/// takes the fields in [workspace.package] from the workspace (`edition.workspace = true`)
+/// or, with inherit set to false, gets them written out instead. Shows the new manifest
+/// and any change to the workspace's.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_new")]
+pub struct CargoNew {
+    /// Directory for the package, relative to the working directory, e.g. 'crates/parser'
+    /// ('.' with init to make the working directory itself a package)
+    pub path: String,
+
+    /// Create the package in an existing directory, keeping its files (cargo init)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub init: Option<bool>,
+
+    /// Create a library rather than a binary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub lib: Option<bool>,
+
+    /// Optional package name (defaults to the directory name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Optional Rust edition, e.g. '2024'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub edition: Option<String>,
+
+    /// Version control to set up: git, hg, pijul, fossil or none (cargo skips it inside an
+    /// existing repository)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub vcs: Option<String>,
+
+    /// Inside a workspace, whether the package should inherit the fields in
+    /// [workspace.package] (true) or have them copied in (false). Leave out to keep what
+    /// cargo generates, which recent versions inherit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub inherit: Option<bool>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoNew {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Add a library crate to the workspace",
+                item: Self {
+                    path: "crates/parser".into(),
+                    init: None,
+                    lib: Some(true),
+                    name: None,
+                    edition: None,
+                    vcs: None,
+                    inherit: Some(true),
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Create a binary package with its own git repository",
+                item: Self {
+                    path: "my-tool".into(),
+                    init: None,
+                    lib: None,
+                    name: None,
+                    edition: Some("2024".into()),
+                    vcs: Some("git".into()),
+                    inherit: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Turn the working directory into a library package",
+                item: Self {
+                    path: ".".into(),
+                    init: Some(true),
+                    lib: Some(true),
+                    name: Some("my-lib".into()),
+                    edition: None,
+                    vcs: Some("none".into()),
+                    inherit: None,
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoNew {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let working_directory = state
+            .get_context(None)?
+            .ok_or_else(|| anyhow!("No working directory set. Use set_working_directory first."))?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let package_dir = resolve_inside(&working_directory, &self.path).ok_or_else(|| {
+            anyhow!(
+                "{} is outside the working directory {}; cargo_new only creates packages \
+                 inside it",
+                self.path,
+                working_directory.display()
+            )
+        })?;
+        let init = self.init.unwrap_or(false);
+
+        // Adding to a workspace edits its manifest, so that must be inside the working
+        // directory too
+        let workspace = find_workspace(&package_dir);
+        if let Some((root, _)) = &workspace
+            && !root.starts_with(&working_directory)
+        {
+            return Err(anyhow!(
+                "{} would join the workspace at {}, which is outside the working directory; \
+                 use set_working_directory to work from the workspace root",
+                self.path,
+                root.display()
+            ));
+        }
+
+        let package_dir_arg = package_dir.to_string_lossy();
+        let mut args = vec![if init { "init" } else { "new" }, &package_dir_arg];
+        args.push(if self.lib.unwrap_or(false) {
+            "--lib"
+        } else {
+            "--bin"
+        });
+        if let Some(name) = &self.name {
+            args.extend_from_slice(&["--name", name]);
+        }
+        if let Some(edition) = &self.edition {
+            args.extend_from_slice(&["--edition", edition]);
+        }
+        if let Some(vcs) = &self.vcs {
+            args.extend_from_slice(&["--vcs", vcs]);
+        }
+
+        let command_name = if init { "cargo init" } else { "cargo new" };
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), None);
+        let output = run_cargo_command(&mut cmd, &working_directory, command_name, None, state)?;
+        let mut result =
+            render_command_output(&cmd, &working_directory, command_name, &output, state)?;
+        if !output.success() {
+            return Ok(result);
+        }
+
+        let manifest_path = package_dir.join("Cargo.toml");
+        if let Some((root, root_before)) = workspace {
+            let relative = package_dir.strip_prefix(&root).unwrap_or(&package_dir);
+            let root_path = root.join("Cargo.toml");
+            let mut root_manifest = Manifest::load(&root_path)?;
+            let mut manifest = Manifest::load(&manifest_path)?;
+
+            let metadata = load_metadata(&root, toolchain.as_deref(), true, &limits)?;
+            let is_member = metadata
+                .members()
+                .any(|package| package.manifest_path == manifest_path);
+            if !is_member && !is_excluded(&root_manifest, relative) {
+                add_member(&mut root_manifest, relative)?;
+            }
+            if let Some(inherit) = self.inherit {
+                let skip = if self.edition.is_some() {
+                    &["edition"][..]
+                } else {
+                    &[]
+                };
+                wire_workspace_package(&root_manifest, &mut manifest, relative, inherit, skip);
+            }
+
+            let edited: Vec<(&Path, &Manifest)> = [
+                (root_path.as_path(), &root_manifest),
+                (manifest_path.as_path(), &manifest),
+            ]
+            .into_iter()
+            .filter(|(_, manifest)| manifest.is_changed())
+            .collect();
+            if !edited.is_empty() {
+                save_checked(&edited, &metadata, toolchain.as_deref(), false, &limits)?;
+            }
+
+            let root_after = fs::read_to_string(&root_path)?;
+            if root_after != root_before {
+                let _ = writeln!(
+                    result,
+                    "📝 Workspace manifest changes:\n{}",
+                    TextDiff::from_lines(&root_before, &root_after)
+                        .unified_diff()
+                        .header("a/Cargo.toml", "b/Cargo.toml")
+                );
+            }
+        }
+
+        let relative = manifest_path
+            .strip_prefix(&working_directory)
+            .unwrap_or(&manifest_path);
+        let _ = writeln!(
+            result,
+            "📄 {}:\n{}",
+            relative.display(),
+            fs::read_to_string(&manifest_path)?.trim_end()
+        );
+
+        Ok(result)
+    }
+}
+
+/// The nearest directory above `package_dir` whose Cargo.toml has a `[workspace]` table,
+/// with that manifest's text
+fn find_workspace(package_dir: &Path) -> Option<(PathBuf, String)> {
+    package_dir.ancestors().skip(1).find_map(|dir| {
+        let text = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+        let document: toml_edit::DocumentMut = text.parse().ok()?;
+        document
+            .contains_table("workspace")
+            .then(|| (dir.to_path_buf(), text))
+    })
+}
+
+/// Whether a path in `[workspace] exclude` covers the package
+fn is_excluded(root: &Manifest, relative: &Path) -> bool {
+    root.document
+        .get("workspace")
+        .and_then(|workspace| workspace.get("exclude"))
+        .and_then(Item::as_array)
+        .is_some_and(|exclude| {
+            exclude
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|excluded| relative.starts_with(excluded))
+        })
+}
+
+fn add_member(root: &mut Manifest, relative: &Path) -> Result<()> {
+    let member = relative.to_string_lossy().replace('\\', "/");
+    let workspace = root
+        .document
+        .get_mut("workspace")
+        .and_then(Item::as_table_mut)
+        .ok_or_else(|| anyhow!("The workspace root manifest has no [workspace] table"))?;
+    workspace
+        .entry("members")
+        .or_insert_with(|| toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("workspace.members is not an array"))?
+        .push(member);
+    Ok(())
+}
+
+/// Point the package's fields at [workspace.package] (`edition.workspace = true`), or
+/// replace inherited fields with the workspace's values. Paths in `readme` and
+/// `license-file` are relative to the manifest they're in, so copied ones are rebased.
+fn wire_workspace_package(
+    root: &Manifest,
+    manifest: &mut Manifest,
+    relative: &Path,
+    inherit: bool,
+    skip: &[&str],
+) {
+    let Some(shared) = root
+        .document
+        .get("workspace")
+        .and_then(|workspace| workspace.get("package"))
+        .and_then(Item::as_table_like)
+    else {
+        return;
+    };
+    let Some(package) = manifest
+        .document
+        .get_mut("package")
+        .and_then(Item::as_table_mut)
+    else {
+        return;
+    };
+
+    for (key, value) in shared.iter() {
+        if skip.contains(&key) {
+            continue;
+        }
+        if inherit {
+            let mut inherited = InlineTable::new();
+            inherited.insert("workspace", true.into());
+            inherited.set_dotted(true);
+            package.insert(key, Item::Value(Value::InlineTable(inherited)));
+            continue;
+        }
+
+        let inherits = package
+            .get(key)
+            .and_then(|field| field.get("workspace"))
+            .and_then(Item::as_bool)
+            .unwrap_or(false);
+        let Some(value) = value.as_value().filter(|_| inherits) else {
+            continue;
+        };
+        let mut value = value.clone();
+        if matches!(key, "readme" | "license-file")
+            && let Some(path) = value.as_str()
+        {
+            let up = "../".repeat(relative.components().count());
+            value = format!("{up}{path}").into();
+        }
+        value.decor_mut().clear();
+        package.insert(key, Item::Value(value));
+    }
+}
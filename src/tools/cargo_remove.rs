@@ -1,6 +1,7 @@
 use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
-use anyhow::{anyhow, Result};
+use crate::tools::cargo_utils::{command_limits, create_cargo_command, execute_cargo_command};
+use crate::tools::lockfile::DependencySnapshot;
+use anyhow::{Result, anyhow};
 use mcplease::{
     traits::{Tool, WithExamples},
     types::Example,
@@ -30,6 +31,12 @@ pub struct CargoRemove {
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -46,6 +53,7 @@ impl WithExamples for CargoRemove {
                     package: None,
                     dev: None,
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -56,6 +64,7 @@ impl WithExamples for CargoRemove {
                     package: None,
                     dev: None,
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -66,6 +75,7 @@ impl WithExamples for CargoRemove {
                     package: None,
                     dev: Some(true),
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -80,14 +90,15 @@ impl Tool<CargoTools> for CargoRemove {
         }
 
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
-
+        let limits = command_limits(None, state)?;
 
         let mut args = vec!["remove"];
-        
+
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
@@ -101,7 +112,16 @@ impl Tool<CargoTools> for CargoRemove {
             args.push(dep);
         }
 
+        // Snapshot the lockfile and manifests to report what changed
+        let snapshot = DependencySnapshot::take(&project_path, toolchain.as_deref(), &limits).ok();
+
         let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo remove")
+        let mut result =
+            execute_cargo_command(cmd, &project_path, "cargo remove", self.timeout_secs, state)?;
+        if let Some(snapshot) = snapshot {
+            result.push_str(&snapshot.report());
+        }
+
+        Ok(result)
     }
 }
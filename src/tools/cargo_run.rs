@@ -1,10 +1,36 @@
+use crate::command_log::LogStream;
+use crate::jobs::job_result;
 use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
-use anyhow::Result;
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, render_command_output, run_cargo_command_with_input,
+    spawn_job, start_background_job,
+};
+use crate::tools::compile_options::CompileOptions;
+use crate::tools::job_status::render_job_header;
+use crate::tools::metadata::load_metadata;
+use crate::tools::readiness::{ProgramOutput, Readiness};
+use anyhow::{Result, anyhow};
 use mcplease::traits::{Tool, WithExamples};
 use mcplease::types::Example;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Seconds to wait for a detached instance to become ready when no timeout is given
+const DEFAULT_READY_TIMEOUT_SECS: u64 = 60;
+
+/// How often a detached instance is checked for readiness
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lines of each stream shown once a detached instance is ready
+const READY_TAIL_LINES: usize = 20;
 
 /// Run a binary or example from the current package
 #[derive(Default, Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
@@ -25,35 +51,73 @@ pub struct CargoRun {
     #[arg(long)]
     pub example: Option<String>,
 
-    /// Run in release mode (optimized)
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: CompileOptions,
+
+    /// Arguments to pass to the binary after `--`
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
-    pub release: Option<bool>,
+    pub args: Option<Vec<String>>,
 
-    /// Space-separated list of features to activate
+    /// Text to write to the program's stdin, which is closed afterwards. Without stdin or
+    /// stdin_file, the program's stdin is /dev/null.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
-    pub features: Option<String>,
+    pub stdin: Option<String>,
 
-    /// Activate all available features
+    /// File whose contents are written to the program's stdin (relative to the working
+    /// directory)
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
-    pub all_features: Option<bool>,
+    pub stdin_file: Option<PathBuf>,
 
-    /// Do not activate the `default` feature
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
-    pub no_default_features: Option<bool>,
+    pub toolchain: Option<String>,
 
-    /// Arguments to pass to the binary after `--`
+    /// Run in the background and return a job id immediately, to be followed with
+    /// job_status, job_output and job_kill. Background jobs have no timeout unless
+    /// timeout_secs is given.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
-    pub args: Option<Vec<String>>,
+    pub background: Option<bool>,
 
-    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    /// Start the binary detached, return once it is ready and leave it running. Follow it
+    /// with cargo_run_logs, cargo_run_signal and cargo_run_stop.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
-    pub toolchain: Option<String>,
+    pub detach: Option<bool>,
+
+    /// Name of a detached instance, used to refer to it later (defaults to the binary,
+    /// example or package name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Regular expression matched against each line the program writes (cargo's own
+    /// messages are skipped); a detached instance is ready once a line matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub ready_pattern: Option<String>,
+
+    /// Port on localhost; a detached instance is ready once it accepts connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub ready_port: Option<u16>,
+
+    /// Seconds to wait for a detached instance to become ready once cargo has started it;
+    /// building it doesn't count (defaults to 60)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub ready_timeout_secs: Option<u64>,
+
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
 
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -92,8 +156,11 @@ impl WithExamples for CargoRun {
             Example {
                 description: "Run in release mode with specific features",
                 item: Self {
-                    release: Some(true),
-                    features: Some("feature1 feature2".into()),
+                    options: CompileOptions {
+                        release: Some(true),
+                        features: Some("feature1 feature2".into()),
+                        ..CompileOptions::default()
+                    },
                     ..Self::default()
                 },
             },
@@ -106,6 +173,32 @@ impl WithExamples for CargoRun {
                     ..Self::default()
                 },
             },
+            Example {
+                description: "Feed input to a command-line filter",
+                item: Self {
+                    args: Some(vec!["--uppercase".into()]),
+                    stdin: Some("hello\nworld\n".into()),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Start a web server and wait until it accepts connections",
+                item: Self {
+                    bin: Some("server".into()),
+                    detach: Some(true),
+                    name: Some("api".into()),
+                    ready_port: Some(8080),
+                    ..Self::default()
+                },
+            },
+            Example {
+                description: "Start a daemon and wait until it logs that it is ready",
+                item: Self {
+                    detach: Some(true),
+                    ready_pattern: Some("(?i)listening on".into()),
+                    ..Self::default()
+                },
+            },
         ]
     }
 }
@@ -117,7 +210,19 @@ impl Tool<CargoTools> for CargoRun {
         // Use toolchain from args, session default, or none
         let toolchain = self
             .toolchain
+            .clone()
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        // Catch misspelled targets before cargo starts building. If cargo metadata fails,
+        // cargo run will report the problem itself.
+        if let Ok(metadata) = load_metadata(&project_path, toolchain.as_deref(), true, &limits) {
+            metadata.check_run_target(
+                self.package.as_deref(),
+                self.bin.as_deref(),
+                self.example.as_deref(),
+            )?;
+        }
 
         let mut args = vec!["run"];
 
@@ -133,21 +238,14 @@ impl Tool<CargoTools> for CargoRun {
             args.extend_from_slice(&["--example", example]);
         }
 
-        if self.release.unwrap_or(false) {
-            args.push("--release");
-        }
-
-        if let Some(ref features) = self.features {
-            args.extend_from_slice(&["--features", features]);
-        }
-
-        if self.all_features.unwrap_or(false) {
-            args.push("--all-features");
-        }
-
-        if self.no_default_features.unwrap_or(false) {
-            args.push("--no-default-features");
+        // cargo run builds and runs a single binary
+        if let Some(option) = self.options.multi_target_option() {
+            return Err(anyhow!(
+                "{option} selects several packages or targets, which cargo run does not \
+                 support. Use bin or example to pick what to run."
+            ));
         }
+        self.options.push_args(&mut args)?;
 
         // Add separator and binary arguments if provided
         if let Some(ref binary_args) = self.args
@@ -159,7 +257,170 @@ impl Tool<CargoTools> for CargoRun {
             }
         }
 
-        let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo run")
+        let input = self.read_input(&project_path)?;
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+        if self.detach.unwrap_or(false) {
+            return self.start_detached(cmd, &project_path, input, state);
+        }
+
+        if self.background.unwrap_or(false) {
+            return start_background_job(
+                cmd,
+                &project_path,
+                "cargo run",
+                input,
+                self.timeout_secs,
+                state,
+                |cmd, project_path, output, state| {
+                    render_command_output(cmd, project_path, "cargo run", output, state)
+                },
+            );
+        }
+
+        let output = run_cargo_command_with_input(
+            &mut cmd,
+            &project_path,
+            "cargo run",
+            input,
+            self.timeout_secs,
+            state,
+        )?;
+        render_command_output(&cmd, &project_path, "cargo run", &output, state)
+    }
+}
+
+impl CargoRun {
+    /// The input for the program's stdin, if any
+    fn read_input(&self, project_path: &Path) -> Result<Option<Vec<u8>>> {
+        match (&self.stdin, &self.stdin_file) {
+            (Some(_), Some(_)) => Err(anyhow!("Pass either stdin or stdin_file, not both")),
+            (Some(stdin), None) => Ok(Some(stdin.clone().into_bytes())),
+            (None, Some(file)) => {
+                let path = project_path.join(file);
+                std::fs::read(&path)
+                    .map(Some)
+                    .map_err(|e| anyhow!("Could not read stdin_file {}: {e}", path.display()))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn start_detached(
+        &self,
+        cmd: Command,
+        project_path: &Path,
+        input: Option<Vec<u8>>,
+        state: &mut CargoTools,
+    ) -> Result<String> {
+        if self.background.unwrap_or(false) {
+            return Err(anyhow!("Pass either detach or background, not both"));
+        }
+
+        let name = self
+            .name
+            .clone()
+            .or_else(|| self.bin.clone())
+            .or_else(|| self.example.clone())
+            .or_else(|| self.package.clone())
+            .unwrap_or_else(|| "default".into());
+
+        if let Some(id) = state.jobs_mut().running_instance(&name) {
+            return Err(anyhow!(
+                "An instance named {name:?} is already running as job {id}. Stop it with \
+                 cargo_run_stop or pick another name."
+            ));
+        }
+
+        let readiness = Readiness::new(self.ready_pattern.as_deref(), self.ready_port)?;
+        let ready_timeout = Duration::from_secs(
+            self.ready_timeout_secs
+                .unwrap_or(DEFAULT_READY_TIMEOUT_SECS),
+        );
+
+        let id = spawn_job(
+            cmd,
+            project_path,
+            "cargo run",
+            Some(name.clone()),
+            input,
+            self.timeout_secs,
+            state,
+            |cmd, project_path, output, state| {
+                render_command_output(cmd, project_path, "cargo run", output, state)
+            },
+        )?;
+
+        let started = Instant::now();
+        let mut program = ProgramOutput::default();
+        let mut running_since = None;
+        let ready = loop {
+            let job = state.jobs_mut().get_mut(id)?;
+            if !job.is_running() {
+                let mut result = render_job_header(job);
+                result.push_str("❌ Exited before becoming ready\n\n");
+                if let Some(job_result) = job_result(state, id)? {
+                    result.push_str(&job_result);
+                }
+                return Ok(result);
+            }
+
+            let lines = program.read(job);
+            if program.started {
+                running_since.get_or_insert_with(Instant::now);
+            }
+            let ready = readiness.check(lines);
+            if ready.is_some() {
+                break ready;
+            }
+
+            if state.request().cancelled().load(Ordering::SeqCst) {
+                state.jobs_mut().get_mut(id)?.kill();
+                return Err(anyhow!(
+                    "Cancelled while waiting for {name:?} to become ready"
+                ));
+            }
+            if running_since.is_some_and(|since| since.elapsed() >= ready_timeout) {
+                break None;
+            }
+            thread::sleep(READY_POLL_INTERVAL);
+        };
+
+        let job = state.jobs_mut().get_mut(id)?;
+        let mut result = render_job_header(job);
+        match ready {
+            Some(how) => {
+                let _ = writeln!(
+                    result,
+                    "✅ Ready after {:.1}s: {how}\n",
+                    started.elapsed().as_secs_f64()
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    result,
+                    "⚠️  Not ready {}s after it started; the instance is still running\n",
+                    ready_timeout.as_secs()
+                );
+            }
+        }
+
+        for stream in [LogStream::Stdout, LogStream::Stderr] {
+            let tail = job.tail(stream, READY_TAIL_LINES);
+            if !tail.is_empty() {
+                let _ = writeln!(
+                    result,
+                    "📤 Recent {}:\n{}\n",
+                    stream.name(),
+                    tail.join("\n")
+                );
+            }
+        }
+
+        let _ = writeln!(
+            result,
+            "Use cargo_run_logs, cargo_run_signal and cargo_run_stop with name {name:?} to \
+             follow, signal and stop it."
+        );
+        Ok(result)
     }
 }
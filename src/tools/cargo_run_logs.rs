@@ -0,0 +1,110 @@
+use crate::command_log::LogStream;
+use crate::state::CargoTools;
+use crate::tools::job_status::render_job_header;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Number of lines shown per stream when no line count is given
+const DEFAULT_LINES: usize = 50;
+
+/// Show the most recent output of a detached cargo_run instance
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_run_logs")]
+pub struct CargoRunLogs {
+    /// Instance to show (defaults to the only running instance)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Number of lines to show from the end of each stream (defaults to 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub lines: Option<usize>,
+
+    /// Only show this stream (defaults to both)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, value_enum)]
+    pub stream: Option<LogStream>,
+
+    /// Only show lines matching this regular expression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub pattern: Option<String>,
+}
+
+impl WithExamples for CargoRunLogs {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Show recent output of the running instance",
+                item: Self {
+                    name: None,
+                    lines: None,
+                    stream: None,
+                    pattern: None,
+                },
+            },
+            Example {
+                description: "Show the last 20 error lines logged by the \"api\" instance",
+                item: Self {
+                    name: Some("api".into()),
+                    lines: Some(20),
+                    stream: None,
+                    pattern: Some("(?i)error".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoRunLogs {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let pattern = self
+            .pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| anyhow!("Invalid pattern: {e}"))?;
+        let count = self.lines.unwrap_or(DEFAULT_LINES);
+        let streams = match self.stream {
+            Some(stream) => vec![stream],
+            None => vec![LogStream::Stdout, LogStream::Stderr],
+        };
+
+        let job = state.jobs_mut().instance_mut(self.name.as_deref())?;
+        let mut result = render_job_header(job);
+
+        for stream in streams {
+            let lines: Vec<String> = job
+                .tail(stream, usize::MAX)
+                .into_iter()
+                .filter(|line| pattern.as_ref().is_none_or(|regex| regex.is_match(line)))
+                .collect();
+            let shown = &lines[lines.len().saturating_sub(count)..];
+
+            if shown.is_empty() {
+                let _ = writeln!(result, "📤 {}: nothing to show\n", stream.name());
+                continue;
+            }
+            let _ = writeln!(
+                result,
+                "📤 Last {} of {} {} lines:",
+                shown.len(),
+                lines.len(),
+                stream.name()
+            );
+            for line in shown {
+                let _ = writeln!(result, "{line}");
+            }
+            result.push('\n');
+        }
+
+        Ok(result)
+    }
+}
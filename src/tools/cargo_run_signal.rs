@@ -0,0 +1,92 @@
+use crate::state::CargoTools;
+use crate::tools::job_status::render_job_header;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Send a signal to a detached cargo_run instance
+///
+/// On unix `cargo run` replaces itself with the program it runs, so once the program has
+/// started the signal reaches it rather than cargo. Sent while cargo is still building the
+/// program, it reaches cargo instead. Unix only.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_run_signal")]
+pub struct CargoRunSignal {
+    /// Signal to send, by name (HUP, INT, TERM, KILL, USR1, USR2, QUIT; with or without
+    /// the SIG prefix) or number
+    pub signal: String,
+
+    /// Instance to signal (defaults to the only running instance)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub name: Option<String>,
+}
+
+impl WithExamples for CargoRunSignal {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Ask the running instance to reload its configuration",
+                item: Self {
+                    signal: "HUP".into(),
+                    name: None,
+                },
+            },
+            Example {
+                description: "Interrupt the \"api\" instance as Ctrl-C would",
+                item: Self {
+                    signal: "SIGINT".into(),
+                    name: Some("api".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoRunSignal {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let signal = parse_signal(&self.signal)?;
+        let job = state.jobs_mut().instance_mut(self.name.as_deref())?;
+        job.signal(signal)?;
+
+        let mut result = render_job_header(job);
+        result.push_str(&format!(
+            "📨 Sent {} to the instance; use cargo_run_logs to see how it reacted\n",
+            self.signal
+        ));
+        Ok(result)
+    }
+}
+
+/// Parse a signal name or number
+#[cfg(unix)]
+fn parse_signal(signal: &str) -> Result<i32> {
+    if let Ok(number) = signal.parse() {
+        return Ok(number);
+    }
+
+    let name = signal.to_ascii_uppercase();
+    match name.strip_prefix("SIG").unwrap_or(&name) {
+        "HUP" => Ok(libc::SIGHUP),
+        "INT" => Ok(libc::SIGINT),
+        "QUIT" => Ok(libc::SIGQUIT),
+        "KILL" => Ok(libc::SIGKILL),
+        "TERM" => Ok(libc::SIGTERM),
+        "USR1" => Ok(libc::SIGUSR1),
+        "USR2" => Ok(libc::SIGUSR2),
+        "CONT" => Ok(libc::SIGCONT),
+        "STOP" => Ok(libc::SIGSTOP),
+        _ => Err(anyhow!(
+            "Unknown signal '{signal}'. Use one of HUP, INT, QUIT, KILL, TERM, USR1, USR2, \
+             CONT, STOP or a signal number."
+        )),
+    }
+}
+
+#[cfg(not(unix))]
+fn parse_signal(_signal: &str) -> Result<i32> {
+    Err(anyhow!("Sending signals is only supported on unix"))
+}
@@ -0,0 +1,64 @@
+use crate::jobs::job_result;
+use crate::state::CargoTools;
+use crate::tools::job_status::render_job_header;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stop a detached cargo_run instance
+///
+/// Sends SIGTERM to the instance's process group, then SIGKILL if it has not exited
+/// after a short grace period, and reports the output it produced.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_run_stop")]
+pub struct CargoRunStop {
+    /// Instance to stop (defaults to the only running instance)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub name: Option<String>,
+}
+
+impl WithExamples for CargoRunStop {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Stop the running instance",
+                item: Self { name: None },
+            },
+            Example {
+                description: "Stop the \"api\" instance",
+                item: Self {
+                    name: Some("api".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoRunStop {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let job = state.jobs_mut().instance_mut(self.name.as_deref())?;
+        let id = job.id();
+        if !job.is_running() {
+            let mut result = render_job_header(job);
+            result.push_str("ℹ️  This instance had already exited\n\n");
+            if let Some(job_result) = job_result(state, id)? {
+                result.push_str(&job_result);
+            }
+            return Ok(result);
+        }
+
+        job.kill();
+        let mut result = render_job_header(job);
+        if job.is_running() {
+            result.push_str("⚠️  The instance has not exited yet; check again with job_status\n");
+        } else if let Some(job_result) = job_result(state, id)? {
+            result.push_str(&job_result);
+        }
+
+        Ok(result)
+    }
+}
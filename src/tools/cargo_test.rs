@@ -1,14 +1,26 @@
-use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
+use crate::state::{CapturedDiagnostics, CargoTools};
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, format_command_header, is_nightly, push_output,
+    push_report, run_cargo_command, start_background_job,
+};
+use crate::tools::compile_options::CompileOptions;
+use crate::tools::diagnostics::{parse_diagnostics, plural, render_diagnostics};
+use crate::tools::output::denoise;
+use crate::tools::process::CommandOutput;
+use crate::tools::test_results::{TestStatus, parse_test_output, render_test_report};
 use anyhow::Result;
 use mcplease::{
     traits::{Tool, WithExamples},
     types::Example,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path, process::Command};
 
 /// Run cargo test to execute tests
+///
+/// Results are summarized per test: failures are listed with their panic location,
+/// message and captured output, and passing tests are only counted. Per-test durations
+/// are reported when the toolchain is nightly.
 #[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
 #[serde(rename = "cargo_test")]
 pub struct CargoTest {
@@ -17,6 +29,10 @@ pub struct CargoTest {
     #[arg(long)]
     pub package: Option<String>,
 
+    #[serde(flatten)]
+    #[command(flatten)]
+    pub options: CompileOptions,
+
     /// Optional specific test name to run
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
@@ -27,11 +43,35 @@ pub struct CargoTest {
     #[arg(long)]
     pub no_capture: Option<bool>,
 
+    /// List passing tests individually in the results, not just their count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub include_passed: Option<bool>,
+
+    /// Also list the reported tests (or the diagnostics, if the tests didn't compile) as
+    /// JSON, one object per line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub json: Option<bool>,
+
     /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Run in the background and return a job id immediately, to be followed with
+    /// job_status, job_output and job_kill. Background jobs have no timeout unless
+    /// timeout_secs is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub background: Option<bool>,
+
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -79,6 +119,13 @@ impl WithExamples for CargoTest {
                     ..Self::default()
                 },
             },
+            Example {
+                description: "Run the full test suite as a background job",
+                item: Self {
+                    background: Some(true),
+                    ..Self::default()
+                },
+            },
         ]
     }
 }
@@ -91,23 +138,131 @@ impl Tool<CargoTools> for CargoTest {
         let toolchain = self
             .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
 
-        let mut args = vec!["test"];
+        let mut args = vec!["test", "--message-format=json"];
 
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
+        self.options.push_args(&mut args)?;
+
         if let Some(ref test_name) = self.test_name {
             args.push(test_name);
         }
 
+        let mut test_args = vec![];
+
         // Add --nocapture if requested
         if self.no_capture.unwrap_or(false) {
-            args.extend_from_slice(&["--", "--nocapture"]);
+            test_args.push("--nocapture");
+        }
+
+        // libtest's JSON output includes per-test timings but is only available on nightly
+        if is_nightly(&project_path, toolchain.as_deref(), &limits) {
+            test_args.extend_from_slice(&[
+                "-Z",
+                "unstable-options",
+                "--format",
+                "json",
+                "--report-time",
+            ]);
+        }
+
+        if !test_args.is_empty() {
+            args.push("--");
+            args.extend_from_slice(&test_args);
         }
 
-        let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo test")
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+        let include_passed = self.include_passed.unwrap_or(false);
+        let json = self.json.unwrap_or(false);
+
+        if self.background.unwrap_or(false) {
+            return start_background_job(
+                cmd,
+                &project_path,
+                "cargo test",
+                None,
+                self.timeout_secs,
+                state,
+                move |cmd, project_path, output, state| {
+                    render_test_run(cmd, project_path, output, include_passed, json, state)
+                },
+            );
+        }
+
+        let output = run_cargo_command(
+            &mut cmd,
+            &project_path,
+            "cargo test",
+            self.timeout_secs,
+            state,
+        )?;
+        render_test_run(&cmd, &project_path, &output, include_passed, json, state)
     }
 }
+
+/// Report a test run: compile errors if the tests didn't build, otherwise the results of
+/// each test
+fn render_test_run(
+    cmd: &Command,
+    project_path: &Path,
+    output: &CommandOutput,
+    include_passed: bool,
+    json: bool,
+    state: &mut CargoTools,
+) -> Result<String> {
+    let budget = state.get_output_budget(None)?;
+    let mut result = format_command_header(cmd, project_path, "cargo test", output);
+
+    let diagnostics = parse_diagnostics(&output.stdout);
+    if diagnostics.iter().any(|d| d.is_error()) {
+        result.push_str("🔨 Tests could not be compiled\n\n");
+        push_report(
+            &mut result,
+            &render_diagnostics(&diagnostics, json),
+            budget,
+            output.run_id,
+        );
+        state.set_last_diagnostics(
+            Some(CapturedDiagnostics {
+                command: "cargo test".into(),
+                project_path: project_path.to_path_buf(),
+                diagnostics,
+            }),
+            None,
+        )?;
+        return Ok(result);
+    }
+
+    if !diagnostics.is_empty() {
+        result.push_str(&format!(
+            "⚠️  {} while compiling (run cargo_check for details)\n\n",
+            plural(diagnostics.len(), "compiler warning", "compiler warnings")
+        ));
+    }
+
+    let report = parse_test_output(&output.stdout, &output.stderr);
+    push_report(
+        &mut result,
+        &render_test_report(&report, include_passed, json),
+        budget,
+        output.run_id,
+    );
+
+    // Failures outside of the tests themselves (bad filters, manifest errors, a test
+    // binary that crashed) are only reported on stderr
+    if !output.success() && report.count(TestStatus::Failed) == 0 {
+        push_output(
+            &mut result,
+            "📤 STDERR:",
+            &denoise(&output.stderr),
+            budget,
+            output.run_id,
+        );
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,279 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{
+    create_cargo_command, format_command_header, push_output, run_cargo_command,
+};
+use crate::tools::dependency_tree::{dependency_chains, edges, parse_tree};
+use crate::tools::diagnostics::plural;
+use crate::tools::output::{denoise, split_budget};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write};
+
+/// Chains listed when explaining why an inverted package is in the build
+const MAX_CHAINS: usize = 20;
+
+/// Show the dependency tree using cargo tree
+///
+/// Returns the tree as cargo prints it along with its edges as JSON. With invert, the
+/// tree shows everything that depends on a package, with a summary of how each
+/// workspace member pulls it in; with duplicates, it shows packages that are built in
+/// more than one version and what requires each of them.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_tree")]
+pub struct CargoTree {
+    /// Optional package name to show the tree for (for workspaces)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Show the packages that depend on this package instead (e.g., 'openssl' or
+    /// 'syn@1.0.109')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub invert: Option<String>,
+
+    /// Only show packages that are built in more than one version, and what depends on them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub duplicates: Option<bool>,
+
+    /// Dependency kinds to show: normal, build, dev, all, no-normal, no-build, no-dev,
+    /// no-proc-macro, or features to show which features each dependent enables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub edges: Option<Vec<String>>,
+
+    /// Maximum depth of the tree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub depth: Option<u32>,
+
+    /// Only include dependencies for this target triple, or 'all' for every target
+    /// (defaults to the host)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
+    /// Optional environment variables to set for the cargo command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(skip)]
+    pub cargo_env: Option<HashMap<String, String>>,
+}
+
+impl WithExamples for CargoTree {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Show the direct dependencies of the project",
+                item: Self {
+                    package: None,
+                    invert: None,
+                    duplicates: None,
+                    edges: None,
+                    depth: Some(1),
+                    target: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Find out why openssl is in the build on any platform",
+                item: Self {
+                    package: None,
+                    invert: Some("openssl".into()),
+                    duplicates: None,
+                    edges: None,
+                    depth: None,
+                    target: Some("all".into()),
+                    toolchain: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Find dependencies built in more than one version",
+                item: Self {
+                    package: None,
+                    invert: None,
+                    duplicates: Some(true),
+                    edges: None,
+                    depth: None,
+                    target: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+            Example {
+                description: "Show which features of serde are enabled, and by what",
+                item: Self {
+                    package: None,
+                    invert: Some("serde".into()),
+                    duplicates: None,
+                    edges: Some(vec!["features".into()]),
+                    depth: None,
+                    target: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                    cargo_env: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoTree {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+
+        let mut args = vec!["tree", "--charset", "utf8"];
+
+        if let Some(ref package) = self.package {
+            args.extend_from_slice(&["--package", package]);
+        }
+
+        if let Some(ref invert) = self.invert {
+            args.extend_from_slice(&["--invert", invert]);
+        }
+
+        let duplicates = self.duplicates.unwrap_or(false);
+        if duplicates {
+            args.push("--duplicates");
+        }
+
+        let edge_kinds = self.edges.as_ref().map(|edges| edges.join(","));
+        if let Some(ref edge_kinds) = edge_kinds {
+            args.extend_from_slice(&["--edges", edge_kinds]);
+        }
+
+        let depth = self.depth.map(|depth| depth.to_string());
+        if let Some(ref depth) = depth {
+            args.extend_from_slice(&["--depth", depth]);
+        }
+
+        if let Some(ref target) = self.target {
+            args.extend_from_slice(&["--target", target]);
+        }
+
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+        let output = run_cargo_command(
+            &mut cmd,
+            &project_path,
+            "cargo tree",
+            self.timeout_secs,
+            state,
+        )?;
+        let budget = state.get_output_budget(None)?;
+
+        let mut result = format_command_header(&cmd, &project_path, "cargo tree", &output);
+        if !output.success() {
+            push_output(
+                &mut result,
+                "📤 STDERR:",
+                &denoise(&output.stderr),
+                budget,
+                output.run_id,
+            );
+            return Ok(result);
+        }
+
+        let lines = parse_tree(&output.stdout);
+        if lines.is_empty() {
+            result.push_str(if duplicates {
+                "✅ No package is built in more than one version\n"
+            } else {
+                "ℹ️  Nothing to print\n"
+            });
+            return Ok(result);
+        }
+
+        if duplicates {
+            let mut versions: Vec<(&str, Vec<&str>)> = vec![];
+            for line in lines.iter().filter(|line| line.depth == 0) {
+                let mut words = line.label.split_whitespace();
+                let (Some(name), Some(version)) = (words.next(), words.next()) else {
+                    continue;
+                };
+                match versions.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, found)) => found.push(version),
+                    None => versions.push((name, vec![version])),
+                }
+            }
+            let _ = writeln!(
+                result,
+                "🔁 {} built in more than one version:",
+                plural(versions.len(), "package is", "packages are")
+            );
+            for (name, found) in &versions {
+                let _ = writeln!(result, "  {name}: {}", found.join(", "));
+            }
+            result.push('\n');
+        }
+
+        // Both inverted and duplicate trees list dependents under each package
+        let inverted = self.invert.is_some() || duplicates;
+        let chains = if inverted {
+            dependency_chains(&lines)
+        } else {
+            vec![]
+        };
+        if !chains.is_empty() {
+            let _ = match self.invert {
+                Some(ref invert) => writeln!(result, "🔗 Why {invert} is in the build:"),
+                None => writeln!(result, "🔗 Why each version is in the build:"),
+            };
+            for chain in chains.iter().take(MAX_CHAINS) {
+                let _ = writeln!(result, "  {}", chain.join(" → "));
+            }
+            if chains.len() > MAX_CHAINS {
+                let _ = writeln!(
+                    result,
+                    "  ... and {} more; see the tree below",
+                    chains.len() - MAX_CHAINS
+                );
+            }
+            result.push('\n');
+        }
+
+        let edges = edges(&lines, inverted);
+        let json: String = edges
+            .iter()
+            .filter_map(|edge| serde_json::to_string(edge).ok())
+            .map(|json| json + "\n")
+            .collect();
+
+        let tree = denoise(&output.stdout);
+        let (tree_budget, json_budget) = split_budget(budget, tree.len(), json.len());
+        push_output(&mut result, "🌳 Tree:", &tree, tree_budget, output.run_id);
+        push_output(
+            &mut result,
+            "📋 Edges, from dependent to dependency (JSON, one per line):",
+            &json,
+            json_budget,
+            None,
+        );
+
+        Ok(result)
+    }
+}
@@ -1,5 +1,9 @@
 use crate::state::CargoTools;
-use crate::tools::cargo_utils::{create_cargo_command, execute_cargo_command};
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, render_command_output, run_cargo_command,
+};
+use crate::tools::lockfile::{DependencySnapshot, parse_update_output, render_lock_changes};
+use crate::tools::output::denoise;
 use anyhow::Result;
 use mcplease::{
     traits::{Tool, WithExamples},
@@ -32,6 +36,12 @@ pub struct CargoUpdate {
     #[arg(long)]
     pub toolchain: Option<String>,
 
+    /// Optional timeout in seconds, after which the command and everything it started are
+    /// killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
     /// Optional environment variables to set for the cargo command
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(skip)]
@@ -48,6 +58,7 @@ impl WithExamples for CargoUpdate {
                     dependencies: None,
                     dry_run: None,
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -58,6 +69,7 @@ impl WithExamples for CargoUpdate {
                     dependencies: None,
                     dry_run: Some(true),
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -68,6 +80,7 @@ impl WithExamples for CargoUpdate {
                     dependencies: Some(vec!["serde".into(), "tokio".into()]),
                     dry_run: None,
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -78,6 +91,7 @@ impl WithExamples for CargoUpdate {
                     dependencies: None,
                     dry_run: None,
                     toolchain: None,
+                    timeout_secs: None,
                     cargo_env: None,
                 },
             },
@@ -88,19 +102,21 @@ impl WithExamples for CargoUpdate {
 impl Tool<CargoTools> for CargoUpdate {
     fn execute(self, state: &mut CargoTools) -> Result<String> {
         let project_path = state.ensure_rust_project(None)?;
-        
+
         // Use toolchain from args, session default, or none
-        let toolchain = self.toolchain
+        let toolchain = self
+            .toolchain
             .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
-
+        let limits = command_limits(None, state)?;
 
         let mut args = vec!["update"];
-        
+
         if let Some(ref package) = self.package {
             args.extend_from_slice(&["--package", package]);
         }
 
-        if self.dry_run.unwrap_or(false) {
+        let dry_run = self.dry_run.unwrap_or(false);
+        if dry_run {
             args.push("--dry-run");
         }
 
@@ -111,7 +127,35 @@ impl Tool<CargoTools> for CargoUpdate {
             }
         }
 
-        let cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
-        execute_cargo_command(cmd, &project_path, "cargo update")
+        // Snapshot the lockfile and manifests to report what changed
+        let snapshot = if dry_run {
+            None
+        } else {
+            DependencySnapshot::take(&project_path, toolchain.as_deref(), &limits).ok()
+        };
+
+        let mut cmd = create_cargo_command(&args, toolchain.as_deref(), self.cargo_env.as_ref());
+        let output = run_cargo_command(
+            &mut cmd,
+            &project_path,
+            "cargo update",
+            self.timeout_secs,
+            state,
+        )?;
+        let mut result =
+            render_command_output(&cmd, &project_path, "cargo update", &output, state)?;
+
+        // A dry run leaves Cargo.lock alone, so cargo's own report is all there is
+        if dry_run && output.success() {
+            let changes = parse_update_output(&denoise(&output.stderr));
+            result.push_str(&render_lock_changes(
+                "Changes cargo update would make",
+                &changes,
+            ));
+        } else if let Some(snapshot) = snapshot {
+            result.push_str(&snapshot.report());
+        }
+
+        Ok(result)
     }
 }
@@ -1,5 +1,18 @@
-use anyhow::Result;
-use std::{collections::HashMap, path::PathBuf, process::Command};
+use crate::command_log::{CommandLog, RunRecord};
+use crate::jobs::{JobCommand, Render};
+use crate::state::CargoTools;
+use crate::tools::diagnostics::parse_diagnostics;
+use crate::tools::output::{Truncation, denoise, split_budget, strip_ansi, truncate_to_budget};
+use crate::tools::process::{Capture, CommandOutput, Limits, Process, run_process};
+use crate::tools::progress::{ProgressTracker, enable_progress_bar, strip_progress_bar};
+use anyhow::{Result, anyhow};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// Helper to create a cargo command with optional toolchain and environment variables
 pub fn create_cargo_command(
@@ -28,26 +41,504 @@ pub fn create_cargo_command(
     cmd
 }
 
+/// Longest error returned by [`first_error`]
+const MAX_ERROR_LEN: usize = 160;
+
+/// Seconds a cargo command may run when no timeout is configured
+pub const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+/// The limits commands run for the current tool call are subject to: `timeout_secs`, or
+/// the session's timeout if not given, and the client cancelling the call
+pub fn command_limits(timeout_secs: Option<u64>, state: &mut CargoTools) -> Result<Limits> {
+    let timeout_secs = match timeout_secs {
+        Some(timeout_secs) => timeout_secs,
+        None => state.get_timeout_secs(None)?,
+    };
+    Ok(Limits {
+        timeout: (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs)),
+        cancelled: Arc::clone(state.request().cancelled()),
+    })
+}
+
+/// Resolve `path` against the working directory, or `None` if it leads outside it
+pub fn resolve_inside(working_directory: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(working_directory).ok()?
+    } else {
+        path
+    };
+
+    let mut resolved = working_directory.to_path_buf();
+    for component in relative.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => resolved.push(part),
+            Component::ParentDir if resolved != working_directory => {
+                resolved.pop();
+            }
+            _ => return None,
+        }
+    }
+
+    // A symlink along the way could still lead elsewhere
+    let existing = resolved
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(working_directory);
+    existing
+        .canonicalize()
+        .ok()?
+        .starts_with(working_directory)
+        .then_some(resolved)
+}
+
+/// Run a command in the project directory and capture its output, without logging it
+pub fn capture_output(
+    cmd: &mut Command,
+    project_path: &Path,
+    limits: &Limits,
+) -> Result<CommandOutput> {
+    run_process(cmd, project_path, None, limits)
+}
+
+/// Run a cargo command in the project directory, capture its output and write it to the
+/// session's command log so that it can be read back with `cargo_log`.
+///
+/// The command is killed if it runs longer than `timeout_secs` (or the session's timeout
+/// if not given), or if the client cancels the tool call. If the client asked for
+/// progress notifications, they are sent while the command runs.
+pub fn run_cargo_command(
+    cmd: &mut Command,
+    project_path: &Path,
+    command_name: &str,
+    timeout_secs: Option<u64>,
+    state: &mut CargoTools,
+) -> Result<CommandOutput> {
+    run_cargo_command_with_input(cmd, project_path, command_name, None, timeout_secs, state)
+}
+
+/// Like [`run_cargo_command`], writing `input` to the command's stdin instead of
+/// connecting it to /dev/null
+pub fn run_cargo_command_with_input(
+    cmd: &mut Command,
+    project_path: &Path,
+    command_name: &str,
+    input: Option<Vec<u8>>,
+    timeout_secs: Option<u64>,
+    state: &mut CargoTools,
+) -> Result<CommandOutput> {
+    let limits = command_limits(timeout_secs, state)?;
+
+    // Clients that asked for progress get notifications as cargo's output comes in
+    let started = Instant::now();
+    let mut output = match state.request().progress() {
+        Some(reporter) => {
+            enable_progress_bar(cmd);
+            let process = Process::spawn(cmd, project_path, input)?;
+            let mut tracker = ProgressTracker::new(reporter.clone(), process.buffers());
+            let mut output = process.wait_with(&limits, || tracker.update())?;
+            output.stderr = strip_progress_bar(&output.stderr);
+            output
+        }
+        None => run_process(cmd, project_path, input, &limits)?,
+    };
+
+    let session_id = state.default_session_id();
+    log_output(
+        state.command_log(),
+        session_id,
+        command_name,
+        format_command(cmd),
+        project_path,
+        started.elapsed(),
+        &mut output,
+    );
+
+    Ok(output)
+}
+
+/// Bytes of each stream a background job keeps in memory. Detached instances can run
+/// for a long time, so only the most recent output is kept; the command log has all of it.
+const JOB_OUTPUT_LIMIT: usize = 4 << 20;
+
+/// The command log's record of a command, before it has finished
+fn run_record(command_name: &str, command: String, project_path: &Path) -> RunRecord {
+    RunRecord {
+        id: 0,
+        command_name: command_name.to_string(),
+        command,
+        project_path: project_path.to_path_buf(),
+        exit_code: None,
+        interruption: None,
+        running: false,
+        finished_at: 0,
+        duration_ms: 0,
+        stdout_lines: 0,
+        stderr_lines: 0,
+    }
+}
+
+/// Fill in how a command ended
+fn set_outcome(record: &mut RunRecord, elapsed: Duration, output: &CommandOutput) {
+    record.exit_code = output.status.code();
+    record.interruption = output.interruption;
+    record.duration_ms = elapsed.as_millis().try_into().unwrap_or(u64::MAX);
+}
+
+/// Write a finished command's output to the command log and note its run id. A command
+/// that ran is still worth reporting if its output couldn't be logged, so failures are
+/// only logged.
+pub fn log_output(
+    command_log: &CommandLog,
+    session_id: &str,
+    command_name: &str,
+    command: String,
+    project_path: &Path,
+    elapsed: Duration,
+    output: &mut CommandOutput,
+) {
+    let mut record = run_record(command_name, command, project_path);
+    set_outcome(&mut record, elapsed, output);
+
+    match command_log.record(session_id, record, &output.stdout, &output.stderr) {
+        Ok(record) => output.run_id = Some(record.id),
+        Err(e) => log::warn!("Could not log output of {command_name}: {e}"),
+    }
+}
+
+/// Complete the command log's record of a job whose output was written to it as the job
+/// ran, and note its run id. As with [`log_output`], failures are only logged.
+pub fn finish_log(
+    command_log: &CommandLog,
+    session_id: &str,
+    mut record: RunRecord,
+    (stdout_lines, stderr_lines): (usize, usize),
+    elapsed: Duration,
+    output: &mut CommandOutput,
+) {
+    set_outcome(&mut record, elapsed, output);
+    record.stdout_lines = stdout_lines;
+    record.stderr_lines = stderr_lines;
+
+    let command_name = record.command_name.clone();
+    match command_log.finish(session_id, record) {
+        Ok(record) => output.run_id = Some(record.id),
+        Err(e) => log::warn!("Could not log output of {command_name}: {e}"),
+    }
+}
+
+/// Start a cargo command as a background job and describe how to follow it. `input` is
+/// written to its stdin, as with [`Process::spawn`].
+///
+/// Background jobs have no timeout unless `timeout_secs` is given. Once the job finishes,
+/// `render` turns its output into the result the tool would have returned had it run in
+/// the foreground, which `job_status` then reports.
+pub fn start_background_job(
+    cmd: Command,
+    project_path: &Path,
+    command_name: &'static str,
+    input: Option<Vec<u8>>,
+    timeout_secs: Option<u64>,
+    state: &mut CargoTools,
+    render: impl FnOnce(&Command, &Path, &CommandOutput, &mut CargoTools) -> Result<String> + 'static,
+) -> Result<String> {
+    let id = spawn_job(
+        cmd,
+        project_path,
+        command_name,
+        None,
+        input,
+        timeout_secs,
+        state,
+        render,
+    )?;
+
+    Ok(format!(
+        "🚀 Started job {id}: {command_name}\n📁 Working directory: {}\n\n\
+         Use job_status to check on it (it reports the full result once the job finishes), \
+         job_output to read its output so far, and job_kill to stop it.\n",
+        project_path.display()
+    ))
+}
+
+/// Spawn a cargo command as a background job, optionally as a named instance and with
+/// input for its stdin, returning the job id
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_job(
+    mut cmd: Command,
+    project_path: &Path,
+    command_name: &'static str,
+    instance: Option<String>,
+    input: Option<Vec<u8>>,
+    timeout_secs: Option<u64>,
+    state: &mut CargoTools,
+    render: impl FnOnce(&Command, &Path, &CommandOutput, &mut CargoTools) -> Result<String> + 'static,
+) -> Result<u64> {
+    let command_log = state.command_log().clone();
+    let session_id = state.default_session_id();
+    let command_line = format_command(&cmd);
+
+    // The job's output goes to the command log as it comes in, so that all of it can be
+    // read there while only the most recent output is kept in memory
+    let (record, files) = match command_log.start(
+        session_id,
+        run_record(command_name, command_line.clone(), project_path),
+    ) {
+        Ok((record, stdout, stderr)) => (Some(record), Some((stdout, stderr))),
+        Err(e) => {
+            log::warn!("Could not log output of {command_name}: {e}");
+            (None, None)
+        }
+    };
+    let capture = Capture {
+        limit: Some(JOB_OUTPUT_LIMIT),
+        files,
+    };
+    let process = match Process::spawn_with(&mut cmd, project_path, input, capture) {
+        Ok(process) => process,
+        Err(e) => {
+            if let Some(record) = record {
+                command_log.discard(session_id, record.id);
+            }
+            return Err(e);
+        }
+    };
+    let limits = Limits {
+        timeout: timeout_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+        cancelled: Arc::default(),
+    };
+    let command = JobCommand {
+        name: command_name,
+        instance,
+        command_line,
+        project_path: project_path.to_path_buf(),
+    };
+    let render_path = project_path.to_path_buf();
+    let render: Render = Box::new(move |output, state| render(&cmd, &render_path, output, state));
+
+    Ok(state.jobs_mut().start(
+        process,
+        limits,
+        command,
+        command_log,
+        session_id,
+        record,
+        render,
+    ))
+}
+
+/// Find the root directory of the workspace containing the project.
+///
+/// Paths in compiler diagnostics are relative to this directory, which is not necessarily
+/// the project directory when working in a workspace member.
+pub fn workspace_root(
+    project_path: &Path,
+    toolchain: Option<&str>,
+    limits: &Limits,
+) -> Result<PathBuf> {
+    let mut cmd = create_cargo_command(
+        &["locate-project", "--workspace", "--message-format", "plain"],
+        toolchain,
+        None,
+    );
+    let output = capture_output(&mut cmd, project_path, limits)?;
+    if !output.success() {
+        return Err(anyhow!(
+            "Could not locate workspace root: {}",
+            output.stderr.trim()
+        ));
+    }
+
+    let manifest = PathBuf::from(output.stdout.trim());
+    manifest
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow!("Unexpected manifest path {}", manifest.display()))
+}
+
+/// Whether the toolchain that cargo will use in the project directory is a nightly build,
+/// which is required for unstable flags such as libtest's JSON output
+pub fn is_nightly(project_path: &Path, toolchain: Option<&str>, limits: &Limits) -> bool {
+    let mut cmd = match toolchain {
+        Some(toolchain) => {
+            let mut cmd = Command::new("rustup");
+            cmd.args(["run", toolchain, "rustc", "--version"]);
+            cmd
+        }
+        None => {
+            let mut cmd = Command::new("rustc");
+            cmd.arg("--version");
+            cmd
+        }
+    };
+
+    capture_output(&mut cmd, project_path, limits)
+        .is_ok_and(|output| output.stdout.contains("-nightly"))
+}
+
+/// Names of the toolchains rustup has installed, e.g. `1.70.0-x86_64-unknown-linux-gnu`
+pub fn installed_toolchains(project_path: &Path, limits: &Limits) -> Result<Vec<String>> {
+    let mut cmd = Command::new("rustup");
+    cmd.args(["toolchain", "list"]);
+    let output = capture_output(&mut cmd, project_path, limits)
+        .map_err(|e| anyhow!("Checking the MSRV needs rustup, which could not be run: {e}"))?;
+    if !output.success() {
+        return Err(anyhow!(
+            "rustup toolchain list failed: {}",
+            output.stderr.trim()
+        ));
+    }
+
+    Ok(output
+        .stdout
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect())
+}
+
+/// The installed toolchain for a rust-version such as `1.70` or `1.70.0`. A two-part
+/// version matches the newest installed patch release.
+pub fn matching_toolchain<'a>(installed: &'a [String], msrv: &str) -> Option<&'a str> {
+    installed
+        .iter()
+        .filter_map(|toolchain| {
+            let version = toolchain.split('-').next()?;
+            let matches = version == msrv || version.strip_prefix(msrv)?.starts_with('.');
+            matches.then_some((toolchain, version))
+        })
+        .max_by_key(|(_, version)| {
+            version
+                .split('.')
+                .map(|part| part.parse::<u32>().unwrap_or(0))
+                .collect::<Vec<_>>()
+        })
+        .map(|(toolchain, _)| toolchain.as_str())
+}
+
 /// Execute a cargo command and format the output for MCP response
 pub fn execute_cargo_command(
     mut cmd: Command,
-    project_path: &PathBuf,
+    project_path: &Path,
+    command_name: &str,
+    timeout_secs: Option<u64>,
+    state: &mut CargoTools,
+) -> Result<String> {
+    let output = run_cargo_command(&mut cmd, project_path, command_name, timeout_secs, state)?;
+    render_command_output(&cmd, project_path, command_name, &output, state)
+}
+
+/// Format a command's output for MCP response: the header, then both streams cleaned up
+/// and truncated to the session's output budget. Only cargo's stderr has its progress lines
+/// collapsed; stdout belongs to the program cargo ran and keeps every line.
+pub fn render_command_output(
+    cmd: &Command,
+    project_path: &Path,
     command_name: &str,
+    output: &CommandOutput,
+    state: &mut CargoTools,
 ) -> Result<String> {
-    cmd.current_dir(project_path);
+    let budget = state.get_output_budget(None)?;
+
+    let stdout = strip_ansi(&output.stdout);
+    let stderr = denoise(&output.stderr);
+    let (stdout_budget, stderr_budget) = split_budget(budget, stdout.len(), stderr.len());
+
+    let mut result = format_command_header(cmd, project_path, command_name, output);
+    push_output(
+        &mut result,
+        "📤 STDOUT:",
+        &stdout,
+        stdout_budget,
+        output.run_id,
+    );
+    push_output(
+        &mut result,
+        "📤 STDERR:",
+        &stderr,
+        stderr_budget,
+        output.run_id,
+    );
 
-    let output = cmd.output()?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.stdout.is_empty() && output.stderr.is_empty() {
+        result.push_str("ℹ️  No output produced\n");
+    }
+
+    Ok(result)
+}
+
+/// Append a titled block of command output, truncated to the budget. If anything was
+/// elided, a note saying so is added after the block, pointing at the full log if there is one.
+pub fn push_output(
+    result: &mut String,
+    title: &str,
+    content: &str,
+    budget: usize,
+    run_id: Option<u64>,
+) {
+    let (content, truncation) = truncate_to_budget(content, budget);
+    push_section(result, title, &content);
+    push_truncation_note(result, truncation, budget, run_id);
+}
 
+/// Append a tool's rendered report, such as its diagnostics or test results, truncated to
+/// the budget with a note like [`push_output`]'s if anything was elided
+pub fn push_report(result: &mut String, report: &str, budget: usize, run_id: Option<u64>) {
+    let (report, truncation) = truncate_to_budget(report, budget);
+    result.push_str(&report);
+    push_truncation_note(result, truncation, budget, run_id);
+}
+
+fn push_truncation_note(
+    result: &mut String,
+    truncation: Option<Truncation>,
+    budget: usize,
+    run_id: Option<u64>,
+) {
+    if let Some(truncation) = truncation {
+        result.push_str(&format!(
+            "✂️  Output truncated to fit the {budget} byte budget: kept {} of {} lines \
+             ({} bytes). Set CARGO_MCP_OUTPUT_BUDGET to change the budget.\n",
+            truncation.kept_lines, truncation.total_lines, truncation.total_bytes
+        ));
+        if let Some(run_id) = run_id {
+            result.push_str(&format!(
+                "🗂️  Use cargo_log with run_id {run_id} to page through or search the full output.\n"
+            ));
+        }
+        result.push('\n');
+    }
+}
+
+/// Format the header shared by all cargo tool responses: command name, working directory,
+/// the command line that was run, and whether it succeeded
+pub fn format_command_header(
+    cmd: &Command,
+    project_path: &Path,
+    command_name: &str,
+    output: &CommandOutput,
+) -> String {
     let mut result = format!("=== {command_name} ===\n");
     result.push_str(&format!(
         "📁 Working directory: {}\n",
         project_path.display()
     ));
-    result.push_str(&format!("🔧 Command: {}\n\n", format_command(&cmd)));
+    result.push_str(&format!("🔧 Command: {}\n", format_command(cmd)));
+    if let Some(run_id) = output.run_id {
+        result.push_str(&format!("🗂️  Run id: {run_id}\n"));
+    }
+    result.push('\n');
 
-    if output.status.success() {
+    if let Some(interruption) = output.interruption {
+        result.push_str(&format!(
+            "{}  Command {interruption}; its process group was killed. Output up to that \
+             point is shown below.\n\n",
+            interruption.icon()
+        ));
+    } else if output.success() {
         result.push_str("✅ Command completed successfully\n\n");
     } else {
         result.push_str(&format!(
@@ -56,29 +547,22 @@ pub fn execute_cargo_command(
         ));
     }
 
-    if !stdout.is_empty() {
-        result.push_str("📤 STDOUT:\n");
-        result.push_str(&stdout);
-        if !stdout.ends_with('\n') {
-            result.push('\n');
-        }
-        result.push('\n');
-    }
+    result
+}
 
-    if !stderr.is_empty() {
-        result.push_str("📤 STDERR:\n");
-        result.push_str(&stderr);
-        if !stderr.ends_with('\n') {
-            result.push('\n');
-        }
-        result.push('\n');
+/// Append a titled block of output, skipping it entirely if the content is empty
+pub fn push_section(result: &mut String, title: &str, content: &str) {
+    if content.is_empty() {
+        return;
     }
 
-    if stdout.is_empty() && stderr.is_empty() {
-        result.push_str("ℹ️  No output produced\n");
+    result.push_str(title);
+    result.push('\n');
+    result.push_str(content);
+    if !content.ends_with('\n') {
+        result.push('\n');
     }
-
-    Ok(result)
+    result.push('\n');
 }
 
 /// Format a command for display
@@ -105,3 +589,37 @@ fn shell_escape(arg: &str) -> String {
         arg.to_string()
     }
 }
+
+/// A one-line description of why a command failed: its first compiler error, or else the
+/// first error cargo printed
+pub fn first_error(output: &CommandOutput) -> String {
+    let error = if let Some(interruption) = output.interruption {
+        format!("command {interruption}")
+    } else if let Some(diagnostic) = parse_diagnostics(&output.stdout)
+        .into_iter()
+        .find(|diagnostic| diagnostic.is_error())
+    {
+        let code = diagnostic
+            .code
+            .map(|code| format!("[{code}]"))
+            .unwrap_or_default();
+        let location = diagnostic
+            .primary
+            .map(|span| format!(" ({}:{}:{})", span.file, span.line, span.column))
+            .unwrap_or_default();
+        format!("error{code}: {}{location}", diagnostic.message)
+    } else {
+        output
+            .stderr
+            .lines()
+            .find(|line| line.starts_with("error"))
+            .unwrap_or("failed without an error message")
+            .to_string()
+    };
+
+    let error = error.replace('\n', " ");
+    match error.char_indices().nth(MAX_ERROR_LEN) {
+        Some((index, _)) => format!("{}…", &error[..index]),
+        None => error,
+    }
+}
@@ -0,0 +1,385 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::{
+    command_limits, create_cargo_command, push_output, run_cargo_command,
+};
+use crate::tools::lockfile::DependencySnapshot;
+use crate::tools::manifest::{Manifest, save_checked};
+use crate::tools::metadata::load_metadata;
+use crate::tools::output::denoise;
+use crate::tools::release::{BumpLevel, bump_version, check_new_version, update_requirement};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Item, Value};
+
+/// Bump a package's version and prepare it for release
+///
+/// Sets the new version in the package's manifest, or in [workspace.package] if the
+/// package inherits its version from there (which bumps every member sharing it), and
+/// points the version requirements of workspace members that depend on it by path at the
+/// new version. Then refreshes Cargo.lock and checks the release: the files that would be
+/// packaged, and cargo publish --dry-run (or, offline, cargo package, which builds the
+/// packaged crate without contacting the registry).
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_version_bump")]
+pub struct CargoVersionBump {
+    /// Which part of the version to bump: major, minor, patch or prerelease
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, value_enum)]
+    pub bump: Option<BumpLevel>,
+
+    /// The new version, instead of bump, e.g. '2.0.0-rc.1'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Optional package to bump (for workspaces)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub package: Option<String>,
+
+    /// Show the changes without writing them or running the release checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub dry_run: Option<bool>,
+
+    /// Run the release checks after bumping (defaults to true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub check: Option<bool>,
+
+    /// Work without network access, using only crates already downloaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub offline: Option<bool>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+
+    /// Optional timeout in seconds for each cargo command, after which it and everything
+    /// it started are killed (defaults to the server's timeout; 0 disables it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl WithExamples for CargoVersionBump {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Preview a minor release of a workspace member",
+                item: Self {
+                    bump: Some(BumpLevel::Minor),
+                    version: None,
+                    package: Some("my-lib".into()),
+                    dry_run: Some(true),
+                    check: None,
+                    offline: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                },
+            },
+            Example {
+                description: "Release a patch version and check it can be published",
+                item: Self {
+                    bump: Some(BumpLevel::Patch),
+                    version: None,
+                    package: None,
+                    dry_run: None,
+                    check: None,
+                    offline: None,
+                    toolchain: None,
+                    timeout_secs: None,
+                },
+            },
+            Example {
+                description: "Set a release candidate version without network access",
+                item: Self {
+                    bump: None,
+                    version: Some("2.0.0-rc.1".into()),
+                    package: None,
+                    dry_run: None,
+                    check: None,
+                    offline: Some(true),
+                    toolchain: None,
+                    timeout_secs: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for CargoVersionBump {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), true, &limits)?;
+        let package = metadata.select_member(self.package.as_deref(), &project_path)?;
+        let current = package.version.as_str();
+        let new_version = match (self.bump, &self.version) {
+            (Some(level), None) => bump_version(current, level)?,
+            (None, Some(version)) => {
+                check_new_version(current, version)?;
+                version.clone()
+            }
+            _ => return Err(anyhow!("Pass either bump or version")),
+        };
+
+        // The root manifest first, then each member's, loaded once even if the root is a
+        // package too
+        let root_path = metadata.workspace_root.join("Cargo.toml");
+        let mut manifests: Vec<(PathBuf, Manifest)> = vec![];
+        for path in std::iter::once(&root_path).chain(metadata.members().map(|p| &p.manifest_path))
+        {
+            if !manifests.iter().any(|(loaded, _)| loaded == path) {
+                manifests.push((path.clone(), Manifest::load(path)?));
+            }
+        }
+        let manifest_of = |manifests: &[(PathBuf, Manifest)], path: &Path| {
+            manifests
+                .iter()
+                .position(|(loaded, _)| loaded == path)
+                .unwrap_or_default()
+        };
+
+        // A version inherited from [workspace.package] is bumped there, for every member
+        // that shares it
+        let inherits_version = |manifest: &Manifest| {
+            manifest
+                .document
+                .get("package")
+                .and_then(|package| package.get("version"))
+                .and_then(|version| version.get("workspace"))
+                .and_then(Item::as_bool)
+                .unwrap_or(false)
+        };
+        let package_manifest = manifest_of(&manifests, &package.manifest_path);
+        let bumped: Vec<(&str, &str)> = if inherits_version(&manifests[package_manifest].1) {
+            let version = manifests[0]
+                .1
+                .document
+                .get_mut("workspace")
+                .and_then(|workspace| workspace.get_mut("package"))
+                .and_then(|package| package.get_mut("version"))
+                .and_then(Item::as_value_mut)
+                .ok_or_else(|| anyhow!("[workspace.package] has no version to bump"))?;
+            set_keeping_decor(version, &new_version);
+            metadata
+                .members()
+                .filter(|member| {
+                    inherits_version(&manifests[manifest_of(&manifests, &member.manifest_path)].1)
+                })
+                .map(|member| (member.name.as_str(), member.version.as_str()))
+                .collect()
+        } else {
+            let version = manifests[package_manifest]
+                .1
+                .document
+                .get_mut("package")
+                .and_then(|package| package.get_mut("version"))
+                .and_then(Item::as_value_mut)
+                .ok_or_else(|| anyhow!("{} has no version to bump", package.name))?;
+            set_keeping_decor(version, &new_version);
+            vec![(package.name.as_str(), current)]
+        };
+
+        // Point path dependencies on the bumped packages at the new version
+        let mut left_alone = vec![];
+        for (path, manifest) in &mut manifests {
+            for table in manifest.dependency_tables_mut() {
+                for (key, item) in table.iter_mut() {
+                    let Some(entry) = item.as_table_like_mut() else {
+                        continue;
+                    };
+                    let name = entry
+                        .get("package")
+                        .and_then(Item::as_str)
+                        .unwrap_or(key.get())
+                        .to_string();
+                    if entry.get("path").is_none() || !bumped.iter().any(|(b, _)| *b == name) {
+                        continue;
+                    }
+                    let Some(version) = entry.get_mut("version").and_then(Item::as_value_mut)
+                    else {
+                        continue;
+                    };
+                    let requirement = version.as_str().unwrap_or_default().to_string();
+                    match update_requirement(&requirement, &new_version) {
+                        Some(updated) => set_keeping_decor(version, &updated),
+                        None => left_alone.push(format!(
+                            "{name} = \"{requirement}\" in {}",
+                            path.strip_prefix(&metadata.workspace_root)
+                                .unwrap_or(path)
+                                .display()
+                        )),
+                    }
+                }
+            }
+        }
+
+        let dry_run = self.dry_run.unwrap_or(false);
+        let mut result = "=== cargo version bump ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}\n", project_path.display());
+        for (name, version) in &bumped {
+            let _ = writeln!(result, "🔖 {name} {version} → {new_version}");
+        }
+        if bumped.len() > 1 {
+            result.push_str("   (these packages share the version in [workspace.package])\n");
+        }
+        if !left_alone.is_empty() {
+            let _ = writeln!(
+                result,
+                "⚠️  Requirements that aren't a single version were left as they are; check \
+                 they allow {new_version}:\n  {}",
+                left_alone.join("\n  ")
+            );
+        }
+        result.push('\n');
+
+        let edited: Vec<(&Path, &Manifest)> = manifests
+            .iter()
+            .filter(|(_, manifest)| manifest.is_changed())
+            .map(|(path, manifest)| (path.as_path(), manifest))
+            .collect();
+        let diff: String = edited
+            .iter()
+            .map(|(path, manifest)| {
+                let relative = path.strip_prefix(&metadata.workspace_root).unwrap_or(path);
+                manifest.diff(&relative.display().to_string())
+            })
+            .collect();
+
+        let snapshot = if dry_run {
+            None
+        } else {
+            Some(DependencySnapshot::take(
+                &project_path,
+                toolchain.as_deref(),
+                &limits,
+            )?)
+        };
+        if let Err(e) = save_checked(&edited, &metadata, toolchain.as_deref(), dry_run, &limits) {
+            return Err(anyhow!("{e}\n\n{result}The rejected changes were:\n{diff}"));
+        }
+        let Some(snapshot) = snapshot else {
+            let _ = writeln!(result, "🔍 Would change:\n{diff}");
+            return Ok(result);
+        };
+
+        let offline = self.offline.unwrap_or(false);
+        let budget = state.get_output_budget(None)?;
+        let mut update_args = vec!["update", "--workspace"];
+        if offline {
+            update_args.push("--offline");
+        }
+        let mut cmd = create_cargo_command(&update_args, toolchain.as_deref(), None);
+        let output = run_cargo_command(
+            &mut cmd,
+            &project_path,
+            "cargo update",
+            self.timeout_secs,
+            state,
+        )?;
+        if !output.success() {
+            push_output(
+                &mut result,
+                "❌ Refreshing Cargo.lock failed:",
+                &denoise(&output.stderr),
+                budget,
+                output.run_id,
+            );
+        }
+        result.push_str(&snapshot.report());
+        if !output.success() {
+            return Ok(result);
+        }
+
+        // Packages with `publish = false` are never released, so there's nothing to check
+        let publishable: Vec<&str> = bumped
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| {
+                metadata
+                    .member(name)
+                    .is_ok_and(|member| member.publish.as_ref().is_none_or(|r| !r.is_empty()))
+            })
+            .collect();
+        if !self.check.unwrap_or(true) || publishable.is_empty() {
+            return Ok(result);
+        }
+
+        let mut common = vec!["--allow-dirty"];
+        for name in &publishable {
+            common.extend_from_slice(&["--package", name]);
+        }
+        if offline {
+            common.push("--offline");
+        }
+        let checks: [(&str, &[&str]); 2] = [
+            ("cargo package --list", &["package", "--list"]),
+            if offline {
+                ("cargo package", &["package"])
+            } else {
+                ("cargo publish --dry-run", &["publish", "--dry-run"])
+            },
+        ];
+        for (name, args) in checks {
+            let args = [args, &common].concat();
+            let mut cmd = create_cargo_command(&args, toolchain.as_deref(), None);
+            let output =
+                run_cargo_command(&mut cmd, &project_path, name, self.timeout_secs, state)?;
+            let stderr = denoise(&output.stderr);
+            result.push('\n');
+            if !output.success() {
+                push_output(
+                    &mut result,
+                    &format!("❌ {name} failed:"),
+                    &stderr,
+                    budget,
+                    output.run_id,
+                );
+            } else if args.contains(&"--list") {
+                push_output(
+                    &mut result,
+                    "📦 Files that would be published:",
+                    &output.stdout,
+                    budget,
+                    output.run_id,
+                );
+            } else {
+                let _ = writeln!(result, "✅ {name} passed");
+                let warnings: Vec<&str> = stderr
+                    .lines()
+                    .filter(|line| line.starts_with("warning:") && !line.contains("dry run"))
+                    .collect();
+                if !warnings.is_empty() {
+                    let _ = writeln!(result, "⚠️  {}", warnings.join("\n⚠️  "));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Replace a string value, keeping the comment and spacing around it
+fn set_keeping_decor(value: &mut Value, text: &str) {
+    let decor = value.decor().clone();
+    *value = Value::from(text);
+    *value.decor_mut() = decor;
+}
@@ -0,0 +1,482 @@
+use crate::state::CargoTools;
+use crate::tools::cargo_utils::command_limits;
+use crate::tools::declarations::{
+    Declaration, KINDS, Location, Source, check_existing, merge, parse_declaration,
+};
+use crate::tools::diagnostics::plural;
+use crate::tools::manifest::{Manifest, save_checked};
+use crate::tools::metadata::load_metadata;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Array, InlineTable, Item, TableLike, Value};
+
+/// Move dependencies that several workspace members declare into [workspace.dependencies]
+///
+/// Finds dependencies declared by two or more members (normal, dev, build or
+/// platform-specific) and plans a [workspace.dependencies] entry for each, using the
+/// highest version requirement when the members' are semver-compatible. Members are
+/// rewritten to `dep = { workspace = true }`, keeping their own features and optional
+/// flags. Members switch to an entry already in [workspace.dependencies] when its
+/// requirement satisfies theirs. Dependencies whose declarations can't be reconciled are
+/// listed with the reason. Shows the plan without writing anything unless apply is set.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "cargo_workspace_dependencies")]
+pub struct CargoWorkspaceDependencies {
+    /// Make the changes (defaults to false, which only shows the plan)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub apply: Option<bool>,
+
+    /// Optional dependencies to consolidate, by the name members declare them under
+    /// (defaults to all that qualify)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub dependencies: Option<Vec<String>>,
+
+    /// How many members must declare a dependency for it to be consolidated (default 2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub min_members: Option<usize>,
+
+    /// Optional Rust toolchain to use (e.g., 'stable', 'nightly', '1.70.0')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub toolchain: Option<String>,
+}
+
+impl WithExamples for CargoWorkspaceDependencies {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Show which dependencies could move to [workspace.dependencies]",
+                item: Self {
+                    apply: None,
+                    dependencies: None,
+                    min_members: None,
+                    toolchain: None,
+                },
+            },
+            Example {
+                description: "Consolidate serde and tokio",
+                item: Self {
+                    apply: Some(true),
+                    dependencies: Some(vec!["serde".into(), "tokio".into()]),
+                    min_members: None,
+                    toolchain: None,
+                },
+            },
+        ]
+    }
+}
+
+/// The outcome for one dependency
+#[derive(Debug)]
+struct Consolidation {
+    name: String,
+    members: Vec<String>,
+    version: Option<String>,
+    features: Vec<String>,
+    /// Members whose version requirement is raised to the shared one
+    raised: Vec<String>,
+    /// The members use an entry that was already in [workspace.dependencies]
+    existing: bool,
+    skipped: Option<String>,
+}
+
+impl Consolidation {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: vec![],
+            version: None,
+            features: vec![],
+            raised: vec![],
+            existing: false,
+            skipped: None,
+        }
+    }
+}
+
+impl Tool<CargoTools> for CargoWorkspaceDependencies {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let project_path = state.ensure_rust_project(None)?;
+
+        // Use toolchain from args, session default, or none
+        let toolchain = self
+            .toolchain
+            .or_else(|| state.get_default_toolchain(None).unwrap_or(None));
+        let limits = command_limits(None, state)?;
+
+        let metadata = load_metadata(&project_path, toolchain.as_deref(), true, &limits)?;
+        let root = metadata.workspace_root.clone();
+        let root_path = root.join("Cargo.toml");
+
+        // The root manifest first, then each member's, loaded once even if the root is a
+        // package too
+        let mut manifests: Vec<(PathBuf, Manifest)> =
+            vec![(root_path.clone(), Manifest::load(&root_path)?)];
+        let mut declarations: BTreeMap<String, Vec<Declaration>> = BTreeMap::new();
+        for package in metadata.members() {
+            let index = match manifests
+                .iter()
+                .position(|(path, _)| *path == package.manifest_path)
+            {
+                Some(index) => index,
+                None => {
+                    let manifest = Manifest::load(&package.manifest_path)?;
+                    manifests.push((package.manifest_path.clone(), manifest));
+                    manifests.len() - 1
+                }
+            };
+            let dir = package.manifest_path.parent().unwrap_or(&root);
+            let document = &manifests[index].1.document;
+            for (location, table) in dependency_tables(document) {
+                for (key, item) in table.iter() {
+                    if let Some(declaration) =
+                        parse_declaration(item, &package.name, index, &location, dir)
+                    {
+                        declarations
+                            .entry(key.to_string())
+                            .or_default()
+                            .push(declaration);
+                    }
+                }
+            }
+        }
+
+        let min_members = self.min_members.unwrap_or(2);
+        let location = Location {
+            target: None,
+            kind: "dependencies",
+        };
+        let existing: BTreeMap<String, Declaration> = manifests[0]
+            .1
+            .document
+            .get("workspace")
+            .and_then(|workspace| workspace.get("dependencies"))
+            .and_then(Item::as_table_like)
+            .map(|table| {
+                table
+                    .iter()
+                    .filter_map(|(key, item)| {
+                        parse_declaration(item, "workspace", 0, &location, &root)
+                            .map(|declaration| (key.to_string(), declaration))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut consolidations = vec![];
+        for (name, group) in &declarations {
+            let members: BTreeSet<&str> = group.iter().map(|d| d.member.as_str()).collect();
+            let wanted = self
+                .dependencies
+                .as_ref()
+                .is_none_or(|wanted| wanted.contains(name));
+            if !wanted || members.len() < min_members {
+                continue;
+            }
+
+            let mut consolidation = Consolidation {
+                members: members.iter().map(|member| member.to_string()).collect(),
+                ..Consolidation::new(name)
+            };
+            if let Some(entry) = existing.get(name) {
+                let mut members = BTreeSet::new();
+                for declaration in group {
+                    if let Err(reason) = check_existing(entry, declaration) {
+                        consolidations.push(Consolidation {
+                            members: vec![declaration.member.clone()],
+                            skipped: Some(reason),
+                            ..Consolidation::new(name)
+                        });
+                        continue;
+                    }
+                    let own: Vec<&String> =
+                        declaration.features.difference(&entry.features).collect();
+                    inherit(
+                        &mut manifests[declaration.manifest].1,
+                        &declaration.location,
+                        name,
+                        &own,
+                        declaration.optional,
+                    );
+                    if declaration.version.is_some() && declaration.version != entry.version {
+                        consolidation.raised.push(format!(
+                            "{} ({})",
+                            declaration.member,
+                            declaration.version.as_deref().unwrap_or_default()
+                        ));
+                    }
+                    members.insert(declaration.member.clone());
+                }
+                if !members.is_empty() {
+                    consolidation.members = members.into_iter().collect();
+                    consolidation.version = entry.version.clone();
+                    consolidation.features = entry.features.iter().cloned().collect();
+                    consolidation.existing = true;
+                    consolidations.push(consolidation);
+                }
+                continue;
+            }
+
+            match merge(group) {
+                Err(reason) => consolidation.skipped = Some(reason),
+                Ok((version, shared_features)) => {
+                    let first = &group[0];
+                    let entry = workspace_entry(
+                        &first.source,
+                        version.as_deref(),
+                        &shared_features,
+                        first.default_features,
+                        &root,
+                    );
+                    manifests[0].1.workspace_dependencies()?.insert(name, entry);
+                    for declaration in group {
+                        let own: Vec<&String> =
+                            declaration.features.difference(&shared_features).collect();
+                        inherit(
+                            &mut manifests[declaration.manifest].1,
+                            &declaration.location,
+                            name,
+                            &own,
+                            declaration.optional,
+                        );
+                        if declaration.version.is_some() && declaration.version != version {
+                            consolidation.raised.push(format!(
+                                "{} ({})",
+                                declaration.member,
+                                declaration.version.as_deref().unwrap_or_default()
+                            ));
+                        }
+                    }
+                    consolidation.version = version;
+                    consolidation.features = shared_features.into_iter().collect();
+                }
+            }
+            consolidations.push(consolidation);
+        }
+
+        let apply = self.apply.unwrap_or(false);
+        let mut result = "=== cargo workspace dependencies ===\n".to_string();
+        let _ = writeln!(result, "📁 Working directory: {}\n", root.display());
+        if consolidations.is_empty() {
+            let _ = writeln!(
+                result,
+                "✅ No dependency is declared separately by {min_members} or more members"
+            );
+            return Ok(result);
+        }
+
+        let (planned, skipped): (Vec<_>, Vec<_>) =
+            consolidations.iter().partition(|c| c.skipped.is_none());
+        if !planned.is_empty() {
+            let count = plural(planned.len(), "dependency", "dependencies");
+            let _ = if apply {
+                writeln!(result, "✅ Moved {count} to [workspace.dependencies]:")
+            } else {
+                writeln!(
+                    result,
+                    "🔍 Plan: move {count} to [workspace.dependencies] (pass apply to make \
+                     the change):"
+                )
+            };
+            for consolidation in &planned {
+                let features = if consolidation.features.is_empty() {
+                    String::new()
+                } else {
+                    format!(" with {}", consolidation.features.join(", "))
+                };
+                let existing = if consolidation.existing {
+                    " (already in [workspace.dependencies])"
+                } else {
+                    ""
+                };
+                let _ = writeln!(
+                    result,
+                    "  {} {}{features}, used by {}{existing}",
+                    consolidation.name,
+                    consolidation.version.as_deref().unwrap_or("(no version)"),
+                    consolidation.members.join(", ")
+                );
+                if !consolidation.raised.is_empty() {
+                    let _ = writeln!(
+                        result,
+                        "    ⬆️  raises the requirement of {}",
+                        consolidation.raised.join(", ")
+                    );
+                }
+            }
+            result.push('\n');
+        }
+        if !skipped.is_empty() {
+            let _ = writeln!(result, "⚠️  Left as they are:");
+            for consolidation in &skipped {
+                let _ = writeln!(
+                    result,
+                    "  {} ({}): {}",
+                    consolidation.name,
+                    consolidation.members.join(", "),
+                    consolidation.skipped.as_deref().unwrap_or_default()
+                );
+            }
+            result.push('\n');
+        }
+
+        let edited: Vec<(&Path, &Manifest)> = manifests
+            .iter()
+            .filter(|(_, manifest)| manifest.is_changed())
+            .map(|(path, manifest)| (path.as_path(), manifest))
+            .collect();
+        if !edited.is_empty() {
+            let diffs: String = edited
+                .iter()
+                .map(|(path, manifest)| {
+                    let relative = path.strip_prefix(&root).unwrap_or(path);
+                    manifest.diff(&relative.display().to_string())
+                })
+                .collect();
+            let warnings =
+                match save_checked(&edited, &metadata, toolchain.as_deref(), !apply, &limits) {
+                    Ok(warnings) => warnings,
+                    Err(e) => {
+                        return Err(anyhow!(
+                            "{e}\n\n{result}The rejected changes were:\n{diffs}"
+                        ));
+                    }
+                };
+            let _ = writeln!(result, "📝 Manifest changes:\n{diffs}");
+            if !warnings.is_empty() {
+                let _ = writeln!(result, "⚠️  cargo warnings:\n{}\n", warnings.join("\n"));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// The dependency tables in a manifest, including platform-specific ones
+fn dependency_tables(document: &toml_edit::DocumentMut) -> Vec<(Location, &dyn TableLike)> {
+    let mut tables = vec![];
+    for kind in KINDS {
+        if let Some(table) = document.get(kind).and_then(Item::as_table_like) {
+            tables.push((Location { target: None, kind }, table));
+        }
+    }
+    if let Some(targets) = document.get("target").and_then(Item::as_table_like) {
+        for (target, item) in targets.iter() {
+            for kind in KINDS {
+                if let Some(table) = item.get(kind).and_then(Item::as_table_like) {
+                    let target = Some(target.to_string());
+                    tables.push((Location { target, kind }, table));
+                }
+            }
+        }
+    }
+    tables
+}
+
+fn dependency_table<'a>(
+    document: &'a mut toml_edit::DocumentMut,
+    location: &Location,
+) -> Option<&'a mut dyn TableLike> {
+    let parent = match &location.target {
+        Some(target) => document
+            .get_mut("target")?
+            .get_mut(target)?
+            .as_table_like_mut()?,
+        None => document.as_table_mut(),
+    };
+    parent.get_mut(location.kind)?.as_table_like_mut()
+}
+
+/// The [workspace.dependencies] entry, written like cargo_add writes one
+fn workspace_entry(
+    source: &Source,
+    version: Option<&str>,
+    features: &BTreeSet<String>,
+    default_features: bool,
+    root: &Path,
+) -> Item {
+    // Paths in the root manifest are relative to the workspace root
+    let path = source.path.as_ref().map(|path| {
+        path.strip_prefix(root)
+            .map(|relative| relative.display().to_string())
+            .unwrap_or_else(|_| path.display().to_string())
+    });
+
+    let mut table = InlineTable::new();
+    let fields = [
+        ("package", source.package.clone()),
+        ("version", version.map(str::to_string)),
+        ("path", path),
+        ("git", source.git.clone()),
+        ("branch", source.branch.clone()),
+        ("tag", source.tag.clone()),
+        ("rev", source.rev.clone()),
+        ("registry", source.registry.clone()),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            table.insert(key, Value::from(value));
+        }
+    }
+    if !default_features {
+        table.insert("default-features", Value::from(false));
+    }
+    if !features.is_empty() {
+        table.insert("features", Value::Array(features.iter().collect::<Array>()));
+    }
+
+    match (table.len(), table.get("version")) {
+        (1, Some(version)) => Item::Value(version.clone()),
+        _ => Item::Value(Value::InlineTable(table)),
+    }
+}
+
+/// Rewrite a member's entry to inherit the workspace's, keeping its own features and
+/// whether it's optional
+fn inherit(
+    manifest: &mut Manifest,
+    location: &Location,
+    name: &str,
+    features: &[&String],
+    optional: bool,
+) {
+    let Some(table) = dependency_table(&mut manifest.document, location) else {
+        return;
+    };
+
+    let mut entry = InlineTable::new();
+    entry.insert("workspace", Value::from(true));
+    if !features.is_empty() {
+        entry.insert(
+            "features",
+            Value::Array(features.iter().copied().collect::<Array>()),
+        );
+    }
+    if optional {
+        entry.insert("optional", Value::from(true));
+    }
+
+    match table.get_mut(name) {
+        Some(item @ Item::Table(_)) => *item = Item::Table(entry.into_table()),
+        Some(item) => {
+            let mut value = Value::InlineTable(entry);
+            if let Some(existing) = item.as_value() {
+                *value.decor_mut() = existing.decor().clone();
+            }
+            *item = Item::Value(value);
+        }
+        None => {}
+    }
+}
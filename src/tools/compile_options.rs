@@ -0,0 +1,132 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// Feature, profile, platform and target selection shared by every tool that compiles the
+/// project, flattened into each tool's parameters
+#[derive(Debug, Default, Clone, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+pub struct CompileOptions {
+    /// Space-separated list of features to activate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub features: Option<String>,
+
+    /// Activate all available features
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub all_features: Option<bool>,
+
+    /// Do not activate the `default` feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub no_default_features: Option<bool>,
+
+    /// Build with the release profile (optimized)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub release: Option<bool>,
+
+    /// Build with the given profile (e.g., 'release', 'bench', or a custom profile)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Build for the given target triple (e.g., 'wasm32-unknown-unknown')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Act on every package in the workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub workspace: Option<bool>,
+
+    /// Packages to leave out when acting on the whole workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub exclude: Option<Vec<String>>,
+
+    /// Include all targets: the library, binaries, examples, tests and benches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub all_targets: Option<bool>,
+
+    /// Only the library target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub lib: Option<bool>,
+
+    /// Only binary targets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub bins: Option<bool>,
+
+    /// Only test targets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub tests: Option<bool>,
+
+    /// Only example targets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub examples: Option<bool>,
+}
+
+impl CompileOptions {
+    /// Append these options to a cargo command line
+    pub fn push_args<'a>(&'a self, args: &mut Vec<&'a str>) -> Result<()> {
+        if self.release.unwrap_or(false) && self.profile.is_some() {
+            return Err(anyhow!("Pass either release or profile, not both"));
+        }
+
+        if let Some(ref features) = self.features {
+            args.extend_from_slice(&["--features", features]);
+        }
+
+        let flags = [
+            (self.all_features, "--all-features"),
+            (self.no_default_features, "--no-default-features"),
+            (self.release, "--release"),
+            (self.workspace, "--workspace"),
+            (self.all_targets, "--all-targets"),
+            (self.lib, "--lib"),
+            (self.bins, "--bins"),
+            (self.tests, "--tests"),
+            (self.examples, "--examples"),
+        ];
+        for (enabled, flag) in flags {
+            if enabled.unwrap_or(false) {
+                args.push(flag);
+            }
+        }
+
+        if let Some(ref profile) = self.profile {
+            args.extend_from_slice(&["--profile", profile]);
+        }
+
+        if let Some(ref target) = self.target {
+            args.extend_from_slice(&["--target", target]);
+        }
+
+        for package in self.exclude.iter().flatten() {
+            args.extend_from_slice(&["--exclude", package]);
+        }
+
+        Ok(())
+    }
+
+    /// The first option that selects packages or several targets, which commands that run
+    /// a single binary (`cargo run`) do not accept
+    pub fn multi_target_option(&self) -> Option<&'static str> {
+        [
+            (self.workspace, "workspace"),
+            (self.exclude.as_ref().map(|_| true), "exclude"),
+            (self.all_targets, "all_targets"),
+            (self.lib, "lib"),
+            (self.bins, "bins"),
+            (self.tests, "tests"),
+            (self.examples, "examples"),
+        ]
+        .into_iter()
+        .find_map(|(enabled, name)| enabled.unwrap_or(false).then_some(name))
+    }
+}
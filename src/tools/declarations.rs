@@ -0,0 +1,211 @@
+use crate::tools::lockfile::{compare_versions, semver_compatible};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Item, Value};
+
+/// Where a dependency is declared: `dependencies`, `dev-dependencies` or
+/// `build-dependencies`, optionally under `[target.'cfg(..)']`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub target: Option<String>,
+    pub kind: &'static str,
+}
+
+pub const KINDS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Where a dependency comes from; members must agree on this to share an entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Source {
+    pub package: Option<String>,
+    pub path: Option<PathBuf>,
+    pub git: Option<String>,
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    pub rev: Option<String>,
+    pub registry: Option<String>,
+}
+
+/// One member's declaration of a dependency
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub member: String,
+    pub manifest: usize,
+    pub location: Location,
+    pub source: Source,
+    pub version: Option<String>,
+    pub features: BTreeSet<String>,
+    pub default_features: bool,
+    pub optional: bool,
+}
+
+/// Read a member's dependency entry, or `None` for one that already inherits from the
+/// workspace
+pub fn parse_declaration(
+    item: &Item,
+    member: &str,
+    manifest: usize,
+    location: &Location,
+    dir: &Path,
+) -> Option<Declaration> {
+    let mut declaration = Declaration {
+        member: member.to_string(),
+        manifest,
+        location: location.clone(),
+        source: Source::default(),
+        version: None,
+        features: BTreeSet::new(),
+        default_features: true,
+        optional: false,
+    };
+    if let Some(version) = item.as_str() {
+        declaration.version = Some(version.to_string());
+        return Some(declaration);
+    }
+
+    let table = item.as_table_like()?;
+    if table.get("workspace").and_then(Item::as_bool) == Some(true) {
+        return None;
+    }
+    let text = |key: &str| table.get(key).and_then(Item::as_str).map(str::to_string);
+    declaration.version = text("version");
+    declaration.source = Source {
+        package: text("package"),
+        path: text("path").map(|path| {
+            let path = dir.join(path);
+            path.canonicalize().unwrap_or(path)
+        }),
+        git: text("git"),
+        branch: text("branch"),
+        tag: text("tag"),
+        rev: text("rev"),
+        registry: text("registry"),
+    };
+    declaration.features = table
+        .get("features")
+        .and_then(Item::as_array)
+        .map(|features| {
+            features
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    declaration.default_features = table
+        .get("default-features")
+        .or_else(|| table.get("default_features"))
+        .and_then(Item::as_bool)
+        .unwrap_or(true);
+    declaration.optional = table
+        .get("optional")
+        .and_then(Item::as_bool)
+        .unwrap_or(false);
+    Some(declaration)
+}
+
+/// The shared version requirement and features for a dependency, or why its members'
+/// declarations can't share one entry
+pub fn merge(group: &[Declaration]) -> Result<(Option<String>, BTreeSet<String>), String> {
+    let first = &group[0];
+    if group.iter().any(|d| d.source != first.source) {
+        return Err("members get it from different sources or under different names".into());
+    }
+    if group
+        .iter()
+        .any(|d| d.default_features != first.default_features)
+    {
+        return Err("members disagree on default-features".into());
+    }
+
+    let described = |d: &Declaration| {
+        format!(
+            "{} ({})",
+            d.version.as_deref().unwrap_or("no version"),
+            d.member
+        )
+    };
+    let versions: BTreeSet<&str> = group.iter().filter_map(|d| d.version.as_deref()).collect();
+    let version = if versions.len() <= 1 {
+        versions.first().map(|version| version.to_string())
+    } else {
+        // Only plain requirements like "1.2" or "^1.2" can be combined into the highest
+        if versions
+            .iter()
+            .any(|version| plain_requirement(version).is_none())
+        {
+            return Err(format!(
+                "version requirements can't be combined automatically: {}",
+                group.iter().map(described).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        let highest = versions
+            .iter()
+            .copied()
+            .max_by(|a, b| {
+                compare_versions(
+                    plain_requirement(a).unwrap_or(a),
+                    plain_requirement(b).unwrap_or(b),
+                )
+            })
+            .unwrap_or_default();
+        let highest_plain = plain_requirement(highest).unwrap_or(highest);
+        if versions.iter().any(|version| {
+            !semver_compatible(plain_requirement(version).unwrap_or(version), highest_plain)
+        }) {
+            return Err(format!(
+                "semver-incompatible versions: {}",
+                group.iter().map(described).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        Some(highest.to_string())
+    };
+
+    let shared = group
+        .iter()
+        .map(|d| d.features.clone())
+        .reduce(|shared, features| shared.intersection(&features).cloned().collect())
+        .unwrap_or_default();
+    Ok((version, shared))
+}
+
+/// Why a member can't switch to the workspace's existing entry for a dependency: it comes
+/// from a different source, disagrees on default features, or needs a version the
+/// existing requirement doesn't guarantee
+pub fn check_existing(existing: &Declaration, declaration: &Declaration) -> Result<(), String> {
+    if declaration.source != existing.source {
+        return Err("gets it from a different source than [workspace.dependencies]".into());
+    }
+    if declaration.default_features != existing.default_features {
+        return Err("disagrees with [workspace.dependencies] on default-features".into());
+    }
+
+    let (Some(wanted), Some(shared)) = (&declaration.version, &existing.version) else {
+        return Ok(());
+    };
+    let satisfied = wanted == shared
+        || match (plain_requirement(wanted), plain_requirement(shared)) {
+            (Some(wanted), Some(shared)) => {
+                semver_compatible(wanted, shared) && compare_versions(shared, wanted).is_ge()
+            }
+            _ => false,
+        };
+    if satisfied {
+        Ok(())
+    } else {
+        Err(format!(
+            "requires {wanted}, which [workspace.dependencies]'s {shared} doesn't satisfy"
+        ))
+    }
+}
+
+/// The version in a caret requirement written as `1.2` or `^1.2`
+pub fn plain_requirement(requirement: &str) -> Option<&str> {
+    let version = requirement.strip_prefix('^').unwrap_or(requirement);
+    let plain = !version.is_empty()
+        && version
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    plain.then_some(version)
+}
@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+/// Tree-drawing prefixes cargo tree uses for each level of indentation
+const INDENTS: [&str; 4] = ["│   ", "├── ", "└── ", "    "];
+
+/// One entry of `cargo tree`'s output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeLine {
+    pub depth: usize,
+    /// The package or feature, e.g. `syn v2.0.104` or `serde feature "derive"`
+    pub label: String,
+    /// `dev` or `build` for entries under a `[dev-dependencies]` or `[build-dependencies]`
+    /// heading; normal dependencies have none
+    pub kind: Option<String>,
+    /// Marked `(*)`: already shown earlier in the tree, so its dependencies are left out
+    pub deduplicated: bool,
+}
+
+/// An edge of the dependency graph, always from the dependent to its dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: String,
+}
+
+/// Parse the text output of `cargo tree`. Each tree starts with an entry at depth 0;
+/// `--duplicates` and workspaces print several.
+pub fn parse_tree(text: &str) -> Vec<TreeLine> {
+    let mut lines = vec![];
+    // Open dependency-kind headings, with the depth they were printed at
+    let mut sections: Vec<(usize, String)> = vec![];
+
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let mut rest = line;
+        let mut depth = 0;
+        while let Some(stripped) = INDENTS.iter().find_map(|indent| rest.strip_prefix(indent)) {
+            rest = stripped;
+            depth += 1;
+        }
+
+        sections.retain(|(section_depth, _)| *section_depth < depth);
+        if let Some(heading) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+            let kind = heading.strip_suffix("-dependencies").unwrap_or(heading);
+            sections.push((depth, kind.to_string()));
+            continue;
+        }
+
+        let kind = sections
+            .last()
+            .filter(|(section_depth, _)| section_depth + 1 == depth)
+            .map(|(_, kind)| kind.clone());
+        let (label, deduplicated) = match rest.strip_suffix(" (*)") {
+            Some(label) => (label, true),
+            None => (rest, false),
+        };
+        lines.push(TreeLine {
+            depth,
+            label: label.to_string(),
+            kind,
+            deduplicated,
+        });
+    }
+
+    lines
+}
+
+/// The distinct edges of a parsed tree. With `inverted`, each entry is a dependent of the
+/// one above it rather than a dependency, so the edges are flipped back.
+pub fn edges(lines: &[TreeLine], inverted: bool) -> Vec<Edge> {
+    let mut edges = vec![];
+    let mut ancestors: Vec<&str> = vec![];
+    for line in lines {
+        ancestors.truncate(line.depth);
+        if let Some(parent) = ancestors.last() {
+            let (from, to) = if inverted {
+                (line.label.as_str(), *parent)
+            } else {
+                (*parent, line.label.as_str())
+            };
+            let edge = Edge {
+                from: from.to_string(),
+                to: to.to_string(),
+                kind: line.kind.as_deref().unwrap_or("normal").to_string(),
+            };
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+        ancestors.push(&line.label);
+    }
+    edges
+}
+
+/// For an inverted tree, every chain from something that nothing else depends on (a
+/// workspace member) down to the inverted package, e.g. `app → serde_json → serde`
+pub fn dependency_chains(lines: &[TreeLine]) -> Vec<Vec<&str>> {
+    let mut chains = vec![];
+    let mut ancestors: Vec<&str> = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        ancestors.truncate(line.depth);
+        ancestors.push(&line.label);
+
+        let is_leaf = lines
+            .get(index + 1)
+            .is_none_or(|next| next.depth <= line.depth);
+        if is_leaf && !line.deduplicated && line.depth > 0 {
+            chains.push(ancestors.iter().rev().copied().collect());
+        }
+    }
+    chains
+}
@@ -0,0 +1,471 @@
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Write};
+
+/// A single line of cargo's `--message-format=json` output.
+///
+/// Only compiler messages are interesting here; artifacts, build script results and the
+/// final `build-finished` record are ignored.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerMessage {
+        message: RustcDiagnostic,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// rustc's JSON diagnostic format, as documented at
+/// https://doc.rust-lang.org/rustc/json.html
+#[derive(Debug, Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<String>,
+}
+
+/// A normalized compiler diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based position of this diagnostic within the run that produced it
+    pub id: usize,
+    /// error, warning, note, help, ...
+    pub level: String,
+    /// Error code (e.g. E0308) or lint name (e.g. unused_variables)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    /// Primary span location, if the diagnostic has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<Span>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub secondary: Vec<Span>,
+    /// Attached notes and help messages
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub children: Vec<DiagnosticChild>,
+    /// Code changes proposed by the compiler or lint
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// A source location referenced by a diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Span {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// A note or help message attached to a diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiagnosticChild {
+    pub level: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+/// A proposed code change. All edits belong together and must be applied as a unit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The help message the suggestion was attached to
+    pub message: String,
+    /// MachineApplicable, MaybeIncorrect, HasPlaceholders or Unspecified
+    pub applicability: String,
+    pub edits: Vec<SuggestionEdit>,
+}
+
+/// Replace a byte range of a file with new text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SuggestionEdit {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    pub fn is_machine_applicable(&self) -> bool {
+        self.applicability == "MachineApplicable"
+    }
+
+    fn from_rustc(child: &RustcDiagnostic) -> Option<Self> {
+        let edits: Vec<_> = child
+            .spans
+            .iter()
+            .filter_map(|span| {
+                Some(SuggestionEdit {
+                    file: span.file_name.clone(),
+                    line: span.line_start,
+                    column: span.column_start,
+                    byte_start: span.byte_start,
+                    byte_end: span.byte_end,
+                    replacement: span.suggested_replacement.clone()?,
+                })
+            })
+            .collect();
+
+        if edits.is_empty() {
+            return None;
+        }
+
+        let applicability = child
+            .spans
+            .iter()
+            .find_map(|span| span.suggestion_applicability.clone())
+            .unwrap_or_else(|| "Unspecified".into());
+
+        Some(Self {
+            message: child.message.clone(),
+            applicability,
+            edits,
+        })
+    }
+}
+
+impl From<&RustcSpan> for Span {
+    fn from(span: &RustcSpan) -> Self {
+        Self {
+            file: span.file_name.clone(),
+            line: span.line_start,
+            column: span.column_start,
+            line_end: span.line_end,
+            column_end: span.column_end,
+            label: span.label.clone(),
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level.starts_with("error")
+    }
+
+    /// The lint or error code this diagnostic is grouped under
+    pub fn lint(&self) -> &str {
+        self.code.as_deref().unwrap_or("(no lint)")
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.level == "warning"
+    }
+
+    fn from_rustc(id: usize, diagnostic: &RustcDiagnostic) -> Self {
+        let primary = diagnostic.spans.iter().find(|span| span.is_primary);
+        let secondary = diagnostic
+            .spans
+            .iter()
+            .filter(|span| !span.is_primary)
+            .map(Span::from)
+            .collect();
+
+        let (with_suggestions, notes): (Vec<_>, Vec<_>) =
+            diagnostic.children.iter().partition(|child| {
+                child
+                    .spans
+                    .iter()
+                    .any(|span| span.suggested_replacement.is_some())
+            });
+
+        let children = notes
+            .into_iter()
+            .map(|child| DiagnosticChild {
+                level: child.level.clone(),
+                message: child.message.clone(),
+                span: child
+                    .spans
+                    .iter()
+                    .find(|span| span.is_primary)
+                    .or(child.spans.first())
+                    .map(Span::from),
+            })
+            .collect();
+
+        let suggestions = with_suggestions
+            .into_iter()
+            .filter_map(Suggestion::from_rustc)
+            .collect();
+
+        Self {
+            id,
+            level: diagnostic.level.clone(),
+            code: diagnostic.code.as_ref().map(|code| code.code.clone()),
+            message: diagnostic.message.clone(),
+            primary: primary.map(Span::from),
+            secondary,
+            children,
+            suggestions,
+        }
+    }
+
+    /// Is this the same diagnostic, ignoring its id? Used to drop the duplicates that
+    /// appear when one source file is compiled for several targets.
+    fn same_as(&self, other: &Self) -> bool {
+        self.level == other.level
+            && self.code == other.code
+            && self.message == other.message
+            && self.primary == other.primary
+    }
+}
+
+/// rustc's closing remarks ("aborting due to 2 previous errors", "For more information
+/// about this error...") carry no information that isn't already in the diagnostics.
+fn is_summary_message(diagnostic: &RustcDiagnostic) -> bool {
+    diagnostic.spans.is_empty()
+        && (diagnostic.level == "failure-note"
+            || diagnostic.message.starts_with("aborting due to")
+            || diagnostic.message.ends_with("warning emitted")
+            || diagnostic.message.ends_with("warnings emitted"))
+}
+
+/// Parse the stdout of a cargo command run with `--message-format=json` into diagnostics.
+///
+/// Lines that are not JSON (for example the output of a build script or a test binary)
+/// are skipped.
+pub fn parse_diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+
+    for line in stdout.lines().filter(|line| line.starts_with('{')) {
+        let Ok(CargoMessage::CompilerMessage { message }) = serde_json::from_str(line) else {
+            continue;
+        };
+
+        if is_summary_message(&message) {
+            continue;
+        }
+
+        let diagnostic = Diagnostic::from_rustc(diagnostics.len() + 1, &message);
+        if !diagnostics.iter().any(|d| d.same_as(&diagnostic)) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+}
+
+/// One-line count of errors and warnings, e.g. "2 errors, 1 warning"
+pub fn summarize_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.iter().filter(|d| d.is_warning()).count();
+    let other = diagnostics.len() - errors - warnings;
+
+    let mut parts = vec![
+        plural(errors, "error", "errors"),
+        plural(warnings, "warning", "warnings"),
+    ];
+    if other > 0 {
+        parts.push(format!("{other} other"));
+    }
+    parts.join(", ")
+}
+
+pub fn plural(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{count} {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
+/// Compact human-readable rendering of a single diagnostic
+pub fn render_diagnostic(diagnostic: &Diagnostic) -> String {
+    let mut out = format!("[{}] {}", diagnostic.id, diagnostic.level);
+    if let Some(code) = &diagnostic.code {
+        let _ = write!(out, "[{code}]");
+    }
+    let _ = writeln!(out, ": {}", diagnostic.message);
+
+    if let Some(primary) = &diagnostic.primary {
+        let _ = write!(
+            out,
+            "    --> {}:{}:{}",
+            primary.file, primary.line, primary.column
+        );
+        if let Some(label) = &primary.label {
+            let _ = write!(out, " ({label})");
+        }
+        out.push('\n');
+    }
+
+    for span in &diagnostic.secondary {
+        let _ = write!(out, "    {}:{}:{}", span.file, span.line, span.column);
+        if let Some(label) = &span.label {
+            let _ = write!(out, ": {label}");
+        }
+        out.push('\n');
+    }
+
+    for child in &diagnostic.children {
+        let _ = write!(out, "    = {}: {}", child.level, child.message);
+        if let Some(span) = &child.span {
+            let _ = write!(out, " ({}:{}:{})", span.file, span.line, span.column);
+        }
+        out.push('\n');
+    }
+
+    for suggestion in &diagnostic.suggestions {
+        let _ = writeln!(
+            out,
+            "    💡 {} [{}]",
+            suggestion.message, suggestion.applicability
+        );
+        for edit in &suggestion.edits {
+            let _ = writeln!(
+                out,
+                "       {}:{}:{}: `{}`",
+                edit.file, edit.line, edit.column, edit.replacement
+            );
+        }
+    }
+
+    out
+}
+
+/// Render diagnostics as a compact summary, optionally followed by one JSON object per line
+pub fn render_diagnostics(diagnostics: &[Diagnostic], json: bool) -> String {
+    if diagnostics.is_empty() {
+        return "🔍 No diagnostics\n\n".into();
+    }
+
+    let mut out = format!("🔍 {}\n\n", summarize_diagnostics(diagnostics));
+    for diagnostic in diagnostics {
+        out.push_str(&render_diagnostic(diagnostic));
+        out.push('\n');
+    }
+
+    if json {
+        push_json_lines(&mut out, diagnostics);
+    }
+    out
+}
+
+fn push_json_lines(out: &mut String, diagnostics: &[Diagnostic]) {
+    out.push_str("📋 Diagnostics (JSON, one per line):\n");
+    for diagnostic in diagnostics {
+        if let Ok(json) = serde_json::to_string(diagnostic) {
+            out.push_str(&json);
+            out.push('\n');
+        }
+    }
+    out.push('\n');
+}
+
+/// All diagnostics reported for one lint
+#[derive(Debug)]
+pub struct LintGroup<'a> {
+    pub lint: &'a str,
+    pub diagnostics: Vec<&'a Diagnostic>,
+    /// Number of occurrences per file
+    pub files: BTreeMap<&'a str, usize>,
+}
+
+/// Group diagnostics by lint name, most frequent lint first
+pub fn group_by_lint(diagnostics: &[Diagnostic]) -> Vec<LintGroup<'_>> {
+    let mut groups: BTreeMap<&str, LintGroup<'_>> = BTreeMap::new();
+
+    for diagnostic in diagnostics {
+        let lint = diagnostic.lint();
+        let group = groups.entry(lint).or_insert_with(|| LintGroup {
+            lint,
+            diagnostics: vec![],
+            files: BTreeMap::new(),
+        });
+        group.diagnostics.push(diagnostic);
+        if let Some(primary) = &diagnostic.primary {
+            *group.files.entry(primary.file.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut groups: Vec<_> = groups.into_values().collect();
+    groups.sort_by_key(|group| Reverse(group.diagnostics.len()));
+    groups
+}
+
+/// Render diagnostics grouped by lint with per-lint and per-file counts, optionally followed
+/// by one JSON object per line
+pub fn render_lint_groups(diagnostics: &[Diagnostic], json: bool) -> String {
+    if diagnostics.is_empty() {
+        return "🔍 No diagnostics\n\n".into();
+    }
+
+    let groups = group_by_lint(diagnostics);
+
+    let mut out = format!("🔍 {}\n\n", summarize_diagnostics(diagnostics));
+
+    out.push_str("📊 By lint:\n");
+    for group in &groups {
+        let files = group
+            .files
+            .iter()
+            .map(|(file, count)| format!("{file} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let fixable = group
+            .diagnostics
+            .iter()
+            .filter(|d| d.suggestions.iter().any(Suggestion::is_machine_applicable))
+            .count();
+        let _ = writeln!(
+            out,
+            "  {} ({}, {fixable} machine-applicable): {files}",
+            group.lint,
+            group.diagnostics.len()
+        );
+    }
+    out.push('\n');
+
+    let mut files: BTreeMap<&str, usize> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        if let Some(primary) = &diagnostic.primary {
+            *files.entry(primary.file.as_str()).or_default() += 1;
+        }
+    }
+    out.push_str("📊 By file:\n");
+    for (file, count) in files {
+        let _ = writeln!(out, "  {file}: {count}");
+    }
+    out.push('\n');
+
+    for group in &groups {
+        let _ = writeln!(out, "── {} ──", group.lint);
+        for diagnostic in &group.diagnostics {
+            out.push_str(&render_diagnostic(diagnostic));
+        }
+        out.push('\n');
+    }
+
+    if json {
+        push_json_lines(&mut out, diagnostics);
+    }
+    out
+}
@@ -0,0 +1,131 @@
+/// One set of features to check
+pub struct Combination {
+    pub label: String,
+    pub args: Vec<String>,
+}
+
+impl Combination {
+    pub fn new(label: impl Into<String>, args: &[&str]) -> Self {
+        Self {
+            label: label.into(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// Only the given features, without the default ones
+    pub fn only(features: &[&str]) -> Self {
+        let features = features.join(",");
+        Self::new(
+            features.clone(),
+            &["--no-default-features", "--features", &features],
+        )
+    }
+}
+
+/// The combinations to check, most important first: default features, no default
+/// features, all features, each feature alone, then combinations of up to `depth`
+/// features. Stops at `max` combinations and returns how many more there would have been.
+pub fn combinations(
+    features: Vec<&str>,
+    excluded: &[String],
+    depth: usize,
+    max: usize,
+) -> (Vec<Combination>, usize) {
+    let is_excluded = |feature: &str| excluded.iter().any(|excluded| excluded == feature);
+    let default_excluded = is_excluded("default");
+    let features: Vec<&str> = features
+        .into_iter()
+        .filter(|feature| *feature != "default" && !is_excluded(feature))
+        .collect();
+
+    let mut combinations = vec![];
+    if !default_excluded {
+        combinations.push(Combination::new("default", &[]));
+    }
+    combinations.push(Combination::new(
+        "no default features",
+        &["--no-default-features"],
+    ));
+
+    // The full set is checked here, so the subsets below stop short of it
+    let mut depth = depth.min(features.len());
+    if !features.is_empty() {
+        if excluded.is_empty() {
+            combinations.push(Combination::new("all features", &["--all-features"]));
+        } else {
+            let mut all = features.clone();
+            if !default_excluded {
+                all.push("default");
+            }
+            let mut combination = Combination::only(&all);
+            combination.label = "all features except excluded".into();
+            combinations.push(combination);
+        }
+        depth = depth.min(features.len() - 1);
+    }
+
+    let mut skipped = combinations.len().saturating_sub(max);
+    combinations.truncate(max);
+    for size in 1..=depth {
+        let count = binomial(features.len(), size);
+        let taken = count.min(max - combinations.len());
+        combinations.extend(
+            Subsets::new(&features, size)
+                .take(taken)
+                .map(|subset| Combination::only(&subset)),
+        );
+        skipped = skipped.saturating_add(count - taken);
+    }
+
+    (combinations, skipped)
+}
+
+/// The number of subsets of `size` out of `n` items, or `usize::MAX` if it is larger
+pub fn binomial(n: usize, size: usize) -> usize {
+    let mut count: usize = 1;
+    for i in 0..size {
+        count = match count.checked_mul(n - i) {
+            Some(product) => product / (i + 1),
+            None => return usize::MAX,
+        };
+    }
+    count
+}
+
+/// Every subset of `size` items, in order, generated as they are needed
+pub struct Subsets<'a> {
+    items: &'a [&'a str],
+    /// Indices of the items in the next subset, or `None` once all have been generated
+    indices: Option<Vec<usize>>,
+}
+
+impl<'a> Subsets<'a> {
+    pub fn new(items: &'a [&'a str], size: usize) -> Self {
+        Self {
+            items,
+            indices: (size <= items.len()).then(|| (0..size).collect()),
+        }
+    }
+}
+
+impl<'a> Iterator for Subsets<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = self.indices.as_mut()?;
+        let subset = indices.iter().map(|&index| self.items[index]).collect();
+
+        // Advance the last index that can still move right, and restart those after it
+        let (len, size) = (self.items.len(), indices.len());
+        match (0..size).rev().find(|&i| indices[i] < len - size + i) {
+            Some(i) => {
+                indices[i] += 1;
+                for j in i + 1..size {
+                    indices[j] = indices[j - 1] + 1;
+                }
+            }
+            None => self.indices = None,
+        }
+        Some(subset)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::jobs::job_result;
+use crate::state::CargoTools;
+use crate::tools::job_status::render_job_header;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stop a background job
+///
+/// Kills the job's whole process group and reports the output it produced until then.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "job_kill")]
+pub struct JobKill {
+    /// Job to kill
+    pub job_id: u64,
+}
+
+impl WithExamples for JobKill {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "Kill job 3",
+            item: Self { job_id: 3 },
+        }]
+    }
+}
+
+impl Tool<CargoTools> for JobKill {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let job = state.jobs_mut().get_mut(self.job_id)?;
+        if !job.is_running() {
+            return Ok(format!(
+                "ℹ️  Job {} has already finished: {}\n",
+                self.job_id,
+                job.state().describe()
+            ));
+        }
+
+        job.kill();
+        let mut result = render_job_header(job);
+        if job.is_running() {
+            result.push_str("⚠️  The job has not exited yet; check again with job_status\n");
+        } else if let Some(job_result) = job_result(state, self.job_id)? {
+            result.push_str(&job_result);
+        }
+
+        Ok(result)
+    }
+}
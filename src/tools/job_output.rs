@@ -0,0 +1,167 @@
+use crate::command_log::LogStream;
+use crate::state::CargoTools;
+use crate::tools::output::strip_ansi;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Number of lines returned per stream when no max_lines is given
+const DEFAULT_MAX_LINES: usize = 200;
+
+/// Read a background job's output incrementally
+///
+/// Returns the lines of stdout and stderr written after the given offsets, and the
+/// offsets to pass next time to continue where this call left off. While the job runs,
+/// only complete lines are returned. Jobs only keep their most recent output; older lines
+/// are read with cargo_log under the job's run id.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "job_output")]
+pub struct JobOutput {
+    /// Job to read output from
+    pub job_id: u64,
+
+    /// Number of stdout lines already read (defaults to 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub stdout_offset: Option<usize>,
+
+    /// Number of stderr lines already read (defaults to 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub stderr_offset: Option<usize>,
+
+    /// Maximum number of lines to return from each stream (defaults to 200)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub max_lines: Option<usize>,
+}
+
+impl WithExamples for JobOutput {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Read job 1's output from the start",
+                item: Self {
+                    job_id: 1,
+                    stdout_offset: None,
+                    stderr_offset: None,
+                    max_lines: None,
+                },
+            },
+            Example {
+                description: "Continue reading job 1's output after a previous call",
+                item: Self {
+                    job_id: 1,
+                    stdout_offset: Some(120),
+                    stderr_offset: Some(45),
+                    max_lines: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for JobOutput {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let job = state.jobs_mut().get_mut(self.job_id)?;
+        let running = job.is_running();
+        let max_lines = self.max_lines.unwrap_or(DEFAULT_MAX_LINES).max(1);
+
+        let mut result = format!(
+            "=== job {}: {} ===\n{} ({:.1}s)\n\n",
+            job.id(),
+            job.name(),
+            job.state().describe(),
+            job.elapsed().as_secs_f64()
+        );
+
+        let mut next_offsets = vec![];
+        for (stream, offset) in [
+            (LogStream::Stdout, self.stdout_offset.unwrap_or(0)),
+            (LogStream::Stderr, self.stderr_offset.unwrap_or(0)),
+        ] {
+            let (dropped, text) = job.output(stream);
+            let text = strip_ansi(&text);
+            if offset < dropped {
+                let _ = writeln!(
+                    result,
+                    "ℹ️  The first {dropped} {} lines are no longer kept; read them with \
+                     cargo_log{}\n",
+                    stream.name(),
+                    job.run_id()
+                        .map(|run_id| format!(" (run_id={run_id})"))
+                        .unwrap_or_default()
+                );
+            }
+            let next = dropped
+                + push_lines(
+                    &mut result,
+                    stream,
+                    &text,
+                    offset.saturating_sub(dropped),
+                    max_lines,
+                    dropped,
+                    running,
+                );
+            next_offsets.push(format!("{}_offset={next}", stream.name()));
+        }
+
+        let _ = writeln!(
+            result,
+            "➡️  Continue with {}{}",
+            next_offsets.join(", "),
+            if running {
+                ""
+            } else {
+                " (the job has finished; use job_status for its result)"
+            }
+        );
+
+        Ok(result)
+    }
+}
+
+/// Append up to `max_lines` lines of `text` after the first `offset`, returning the offset
+/// to continue from. `text` starts after `dropped` lines, which only matters for the line
+/// numbers shown. A trailing line without a newline is held back while the job runs, as it
+/// may not be complete.
+fn push_lines(
+    result: &mut String,
+    stream: LogStream,
+    text: &str,
+    offset: usize,
+    max_lines: usize,
+    dropped: usize,
+    running: bool,
+) -> usize {
+    let mut lines: Vec<&str> = text.lines().collect();
+    if running && !text.is_empty() && !text.ends_with('\n') {
+        lines.pop();
+    }
+
+    let end = (offset + max_lines).min(lines.len());
+    if offset >= end {
+        let _ = writeln!(result, "📤 {}: no new lines\n", stream.name());
+        return offset.max(end);
+    }
+
+    let _ = writeln!(
+        result,
+        "📤 {} lines {}-{} of {}:",
+        stream.name(),
+        dropped + offset + 1,
+        dropped + end,
+        dropped + lines.len()
+    );
+    for line in &lines[offset..end] {
+        result.push_str(line);
+        result.push('\n');
+    }
+    result.push('\n');
+
+    end
+}
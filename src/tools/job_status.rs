@@ -0,0 +1,105 @@
+use crate::command_log::LogStream;
+use crate::jobs::{Job, job_result};
+use crate::state::CargoTools;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Check on background jobs
+///
+/// Without a job id, lists every job started in this session. With a job id, reports
+/// that job's state, and once it has finished, the same result its tool would have
+/// returned had it run in the foreground.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "job_status")]
+pub struct JobStatus {
+    /// Job to report on (defaults to listing all jobs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub job_id: Option<u64>,
+}
+
+impl WithExamples for JobStatus {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "List all background jobs",
+                item: Self { job_id: None },
+            },
+            Example {
+                description: "Check on job 2",
+                item: Self { job_id: Some(2) },
+            },
+        ]
+    }
+}
+
+impl Tool<CargoTools> for JobStatus {
+    fn execute(self, state: &mut CargoTools) -> Result<String> {
+        let Some(id) = self.job_id else {
+            let mut result = String::new();
+            for job in state.jobs_mut().list() {
+                let _ = writeln!(
+                    result,
+                    "{:>4}  {}  {} ({:.1}s)  {}",
+                    job.id(),
+                    job.state().describe(),
+                    display_name(job),
+                    job.elapsed().as_secs_f64(),
+                    job.project_path().display()
+                );
+            }
+            if result.is_empty() {
+                return Ok("ℹ️  No background jobs have been started\n".into());
+            }
+            return Ok(format!("=== Background jobs ===\n{result}"));
+        };
+
+        let job = state.jobs_mut().get_mut(id)?;
+        let mut result = render_job_header(job);
+
+        if job.is_running() {
+            let _ = writeln!(
+                result,
+                "📊 {} stdout lines and {} stderr lines so far; use job_output to read them",
+                job.line_count(LogStream::Stdout),
+                job.line_count(LogStream::Stderr),
+            );
+            return Ok(result);
+        }
+
+        if let Some(job_result) = job_result(state, id)? {
+            result.push_str(&job_result);
+        }
+        Ok(result)
+    }
+}
+
+/// Name of a job for display, including its instance name if it has one
+fn display_name(job: &Job) -> String {
+    match job.instance() {
+        Some(instance) => format!("{} [{instance}]", job.name()),
+        None => job.name().to_string(),
+    }
+}
+
+/// Header describing a job: its command, directory, log entry, state and running time
+pub fn render_job_header(job: &Job) -> String {
+    let run_id = job
+        .run_id()
+        .map(|run_id| format!("🗂️  Run id: {run_id}\n"))
+        .unwrap_or_default();
+    format!(
+        "=== job {}: {} ===\n📁 Working directory: {}\n🔧 Command: {}\n{run_id}{} ({:.1}s)\n\n",
+        job.id(),
+        display_name(job),
+        job.project_path().display(),
+        job.command_line(),
+        job.state().describe(),
+        job.elapsed().as_secs_f64(),
+    )
+}
@@ -0,0 +1,176 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use toml_edit::{InlineTable, Item, Table, TableLike, Value};
+
+/// A lint level, as written in `[lints]` tables and passed to rustc
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Forbid,
+}
+
+impl LintLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Deny => "deny",
+            Self::Forbid => "forbid",
+        }
+    }
+}
+
+/// Lint groups, which individual lints should be able to override
+const LINT_GROUPS: &[&str] = &[
+    "warnings",
+    "future_incompatible",
+    "keyword_idents",
+    "let_underscore",
+    "nonstandard_style",
+    "refining_impl_trait",
+    "rust_2018_compatibility",
+    "rust_2018_idioms",
+    "rust_2021_compatibility",
+    "rust_2024_compatibility",
+    "unknown_or_malformed_diagnostic_attributes",
+    "unused",
+    "clippy::all",
+    "clippy::cargo",
+    "clippy::complexity",
+    "clippy::correctness",
+    "clippy::nursery",
+    "clippy::pedantic",
+    "clippy::perf",
+    "clippy::restriction",
+    "clippy::style",
+    "clippy::suspicious",
+];
+
+/// Whether `lint` (e.g. `clippy::pedantic` or `unused`) names a group of lints
+pub fn is_group(lint: &str) -> bool {
+    LINT_GROUPS.contains(&lint.replace('-', "_").as_str())
+}
+
+/// Split a lint into the `[lints]` table it belongs in and its name there, so
+/// `clippy::needless_pass_by_value` is `("clippy", "needless_pass_by_value")` and
+/// `unsafe_code` is `("rust", "unsafe_code")`
+pub fn split_lint(lint: &str) -> (&str, &str) {
+    lint.split_once("::").unwrap_or(("rust", lint))
+}
+
+/// Lint level flags for rustc, e.g. `-W clippy::pedantic -A clippy::too_many_lines`.
+/// Later flags win, so groups are passed before individual lints to let a lint be
+/// singled out from its group whichever lists they're in.
+pub fn lint_args(allow: &[String], warn: &[String], deny: &[String]) -> Vec<String> {
+    let mut flags: Vec<(bool, &str, &str)> = [("-A", allow), ("-W", warn), ("-D", deny)]
+        .into_iter()
+        .flat_map(|(flag, lints)| {
+            lints
+                .iter()
+                .map(move |lint| (!is_group(lint), flag, lint.as_str()))
+        })
+        .collect();
+    // Stable, so lints keep their order within groups and within individual lints
+    flags.sort_by_key(|(individual, _, _)| *individual);
+    flags
+        .into_iter()
+        .flat_map(|(_, flag, lint)| [flag.to_string(), lint.to_string()])
+        .collect()
+}
+
+/// A lint level set in a `[lints]` or `[workspace.lints]` table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintSetting {
+    /// `workspace` for `[workspace.lints]`, otherwise the package's name
+    pub scope: String,
+    pub lint: String,
+    pub level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+}
+
+/// The lint levels set in a `[lints]` or `[workspace.lints]` table
+pub fn lint_settings(scope: &str, lints: &dyn TableLike) -> Vec<LintSetting> {
+    let mut settings = vec![];
+    for (tool, table) in lints.iter() {
+        let Some(table) = table.as_table_like() else {
+            continue;
+        };
+        for (name, entry) in table.iter() {
+            let (level, priority) = match (entry.as_str(), entry.as_table_like()) {
+                (Some(level), _) => (level, None),
+                (None, Some(entry)) => (
+                    entry
+                        .get("level")
+                        .and_then(Item::as_str)
+                        .unwrap_or_default(),
+                    entry.get("priority").and_then(Item::as_integer),
+                ),
+                (None, None) => continue,
+            };
+            settings.push(LintSetting {
+                scope: scope.to_string(),
+                lint: match tool {
+                    "rust" => name.to_string(),
+                    tool => format!("{tool}::{name}"),
+                },
+                level: level.to_string(),
+                priority,
+            });
+        }
+    }
+    settings
+}
+
+/// Set a lint's level in a `[lints]` or `[workspace.lints]` table, or remove it when
+/// `level` is `None`. A group is given priority -1 so that the individual lints in it,
+/// at the default priority of 0, override it; an existing priority is kept.
+pub fn set_lint(lints: &mut Table, lint: &str, level: Option<LintLevel>) -> Result<()> {
+    let (tool, name) = split_lint(lint);
+
+    let Some(level) = level else {
+        let removed = lints
+            .get_mut(tool)
+            .and_then(Item::as_table_like_mut)
+            .and_then(|table| table.remove(name));
+        if removed.is_none() {
+            return Err(anyhow!("{lint} has no level set here"));
+        }
+        if lints
+            .get(tool)
+            .and_then(Item::as_table_like)
+            .is_some_and(|table| table.is_empty())
+        {
+            lints.remove(tool);
+        }
+        return Ok(());
+    };
+
+    let table = lints
+        .entry(tool)
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .ok_or_else(|| anyhow!("lints.{tool} is not a table"))?;
+    let priority = table
+        .get(name)
+        .and_then(Item::as_table_like)
+        .and_then(|entry| entry.get("priority"))
+        .and_then(Item::as_integer)
+        .or(is_group(lint).then_some(-1));
+    let value = match priority {
+        Some(priority) => {
+            let mut entry = InlineTable::new();
+            entry.insert("level", level.as_str().into());
+            entry.insert("priority", priority.into());
+            Value::InlineTable(entry)
+        }
+        None => level.as_str().into(),
+    };
+    table.insert(name, Item::Value(value));
+    Ok(())
+}
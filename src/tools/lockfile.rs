@@ -0,0 +1,326 @@
+use crate::tools::metadata::load_metadata;
+use crate::tools::process::Limits;
+use anyhow::Result;
+use similar::TextDiff;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// How a locked package changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Upgraded,
+    Downgraded,
+}
+
+/// A change to one package in Cargo.lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockChange {
+    pub change: ChangeKind,
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// The versions are semver-incompatible, so the change may break dependents
+    pub semver_major: bool,
+}
+
+impl LockChange {
+    fn new(change: ChangeKind, name: &str, from: Option<&str>, to: Option<&str>) -> Self {
+        Self {
+            change,
+            name: name.to_string(),
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+            semver_major: from
+                .zip(to)
+                .is_some_and(|(from, to)| !semver_compatible(from, to)),
+        }
+    }
+}
+
+/// The name and version of every package in a Cargo.lock, by name
+pub fn parse_lockfile(lockfile: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let mut packages: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut name = None;
+    for line in lockfile.lines() {
+        let line = line.trim();
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(value) = quoted_value(line, "name") {
+            name = Some(value.to_string());
+        } else if let Some(version) = quoted_value(line, "version")
+            && let Some(name) = name.take()
+        {
+            packages
+                .entry(name)
+                .or_default()
+                .insert(version.to_string());
+        }
+    }
+    packages
+}
+
+/// The value of a `key = "value"` line
+fn quoted_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    line.strip_prefix(key)?
+        .trim_start()
+        .strip_prefix('=')?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+}
+
+/// The changes between two versions of Cargo.lock. When a package's versions change, the
+/// old and new versions are paired up oldest first, so `syn 1.0 → 2.0` is reported as an
+/// upgrade while another package keeps depending on `syn 1.0`.
+pub fn diff_lockfiles(before: &str, after: &str) -> Vec<LockChange> {
+    let before = parse_lockfile(before);
+    let after = parse_lockfile(after);
+    let empty = BTreeSet::new();
+
+    let mut changes = vec![];
+    for name in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+        let old = before.get(name).unwrap_or(&empty);
+        let new = after.get(name).unwrap_or(&empty);
+        let mut gone: Vec<&str> = old.difference(new).map(String::as_str).collect();
+        let mut came: Vec<&str> = new.difference(old).map(String::as_str).collect();
+        gone.sort_by(|a, b| compare_versions(a, b));
+        came.sort_by(|a, b| compare_versions(a, b));
+
+        let paired = gone.len().min(came.len());
+        for (from, to) in gone.iter().zip(&came) {
+            let change = if compare_versions(from, to) == Ordering::Greater {
+                ChangeKind::Downgraded
+            } else {
+                ChangeKind::Upgraded
+            };
+            changes.push(LockChange::new(change, name, Some(from), Some(to)));
+        }
+        for from in &gone[paired..] {
+            changes.push(LockChange::new(ChangeKind::Removed, name, Some(from), None));
+        }
+        for to in &came[paired..] {
+            changes.push(LockChange::new(ChangeKind::Added, name, None, Some(to)));
+        }
+    }
+    changes
+}
+
+/// The changes cargo reports while updating the lockfile, e.g. `Updating serde v1.0.1 ->
+/// v1.0.2` or `Adding foo v0.1.0`; with `cargo update --dry-run` this is the only record
+/// of what would change.
+pub fn parse_update_output(stderr: &str) -> Vec<LockChange> {
+    let mut changes = vec![];
+    for line in stderr.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(verb), Some(name), Some(version)) = (words.next(), words.next(), words.next())
+        else {
+            continue;
+        };
+        let Some(version) = version.strip_prefix('v') else {
+            continue;
+        };
+        let target = match (words.next(), words.next()) {
+            (Some("->"), Some(target)) => target.strip_prefix('v'),
+            _ => None,
+        };
+
+        let change = match (verb, target) {
+            ("Updating" | "Upgrading", Some(to)) => {
+                LockChange::new(ChangeKind::Upgraded, name, Some(version), Some(to))
+            }
+            ("Downgrading", Some(to)) => {
+                LockChange::new(ChangeKind::Downgraded, name, Some(version), Some(to))
+            }
+            ("Adding", None) => LockChange::new(ChangeKind::Added, name, None, Some(version)),
+            ("Removing", None) => LockChange::new(ChangeKind::Removed, name, Some(version), None),
+            _ => continue,
+        };
+        changes.push(change);
+    }
+    changes
+}
+
+/// Compare two versions by their numeric parts; a pre-release sorts before its release,
+/// and pre-releases compare identifier by identifier as semver orders them. Build metadata
+/// is ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    version_key(a).cmp(&version_key(b))
+}
+
+/// A pre-release identifier; numeric identifiers compare as numbers and sort before
+/// alphanumeric ones
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier<'a> {
+    Numeric(u64),
+    Alphanumeric(&'a str),
+}
+
+/// A version's numbers, whether it is a release, and its pre-release identifiers
+fn version_key(version: &str) -> (Vec<u64>, bool, Option<Vec<Identifier<'_>>>) {
+    let version = version.split('+').next().unwrap_or_default();
+    let (release, pre) = match version.split_once('-') {
+        Some((release, pre)) => (release, Some(pre)),
+        None => (version, None),
+    };
+    let numbers = release
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    let pre = pre.map(|pre| {
+        pre.split('.')
+            .map(|identifier| match identifier.parse() {
+                Ok(number) => Identifier::Numeric(number),
+                Err(_) => Identifier::Alphanumeric(identifier),
+            })
+            .collect()
+    });
+    (numbers, pre.is_none(), pre)
+}
+
+/// Whether cargo considers two versions compatible: they agree up to and including their
+/// first non-zero component, so 1.2 → 1.9 and 0.3.1 → 0.3.4 are, but 0.3 → 0.4 is not
+pub fn semver_compatible(a: &str, b: &str) -> bool {
+    let key = |version: &str| {
+        let release = version.split(['-', '+']).next().unwrap_or_default();
+        let mut key = vec![];
+        for part in release.split('.') {
+            key.push(part.parse::<u64>().unwrap_or(0));
+            if key.last() != Some(&0) {
+                break;
+            }
+        }
+        key
+    };
+    key(a) == key(b)
+}
+
+/// Packages locked at more than one version, with their versions oldest first
+pub fn duplicates(packages: &BTreeMap<String, BTreeSet<String>>) -> Vec<(&str, Vec<&str>)> {
+    packages
+        .iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| {
+            let mut versions: Vec<&str> = versions.iter().map(String::as_str).collect();
+            versions.sort_by(|a, b| compare_versions(a, b));
+            (name.as_str(), versions)
+        })
+        .collect()
+}
+
+/// Render lockfile changes grouped by kind
+pub fn render_lock_changes(title: &str, changes: &[LockChange]) -> String {
+    let mut out = String::new();
+    if changes.is_empty() {
+        let _ = writeln!(out, "📦 {title}: no packages changed\n");
+        return out;
+    }
+
+    let _ = writeln!(out, "📦 {title}:");
+    let mut changes = changes.to_vec();
+    changes.sort_by(|a, b| (a.change, &a.name).cmp(&(b.change, &b.name)));
+    for change in &changes {
+        let from = change.from.as_deref().unwrap_or_default();
+        let to = change.to.as_deref().unwrap_or_default();
+        let major = if change.semver_major {
+            " (⚠️  semver-major)"
+        } else {
+            ""
+        };
+        let _ = match change.change {
+            ChangeKind::Added => writeln!(out, "  ➕ added {} v{to}", change.name),
+            ChangeKind::Removed => writeln!(out, "  ➖ removed {} v{from}", change.name),
+            ChangeKind::Upgraded => {
+                writeln!(out, "  ⬆️  upgraded {} v{from} → v{to}{major}", change.name)
+            }
+            ChangeKind::Downgraded => {
+                writeln!(
+                    out,
+                    "  ⬇️  downgraded {} v{from} → v{to}{major}",
+                    change.name
+                )
+            }
+        };
+    }
+    out.push('\n');
+    out
+}
+
+/// Cargo.lock and the workspace's manifests as they were before a command that changes
+/// dependencies, to report what the command changed
+#[derive(Debug)]
+pub struct DependencySnapshot {
+    workspace_root: PathBuf,
+    lockfile: Option<String>,
+    manifests: Vec<(PathBuf, String)>,
+}
+
+impl DependencySnapshot {
+    pub fn take(project_path: &Path, toolchain: Option<&str>, limits: &Limits) -> Result<Self> {
+        let metadata = load_metadata(project_path, toolchain, true, limits)?;
+        let root_manifest = metadata.workspace_root.join("Cargo.toml");
+        let mut paths: Vec<PathBuf> = metadata
+            .members()
+            .map(|package| package.manifest_path.clone())
+            .collect();
+        if !paths.contains(&root_manifest) {
+            paths.insert(0, root_manifest);
+        }
+
+        Ok(Self {
+            lockfile: fs::read_to_string(metadata.workspace_root.join("Cargo.lock")).ok(),
+            manifests: paths
+                .into_iter()
+                .filter_map(|path| fs::read_to_string(&path).ok().map(|text| (path, text)))
+                .collect(),
+            workspace_root: metadata.workspace_root,
+        })
+    }
+
+    /// What changed in Cargo.lock and the manifests since the snapshot was taken
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+
+        let lockfile = fs::read_to_string(self.workspace_root.join("Cargo.lock")).ok();
+        if let Some(after) = &lockfile {
+            let before = self.lockfile.as_deref().unwrap_or_default();
+            out.push_str(&render_lock_changes(
+                "Cargo.lock changes",
+                &diff_lockfiles(before, after),
+            ));
+        }
+
+        let mut diffs = String::new();
+        for (path, before) in &self.manifests {
+            let after = fs::read_to_string(path).unwrap_or_default();
+            if after == *before {
+                continue;
+            }
+            let relative = path
+                .strip_prefix(&self.workspace_root)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            let diff = TextDiff::from_lines(before, &after);
+            let _ = write!(
+                diffs,
+                "{}",
+                diff.unified_diff()
+                    .header(&format!("a/{relative}"), &format!("b/{relative}"))
+            );
+        }
+        if diffs.is_empty() {
+            out.push_str("📝 No manifest changes\n");
+        } else {
+            let _ = writeln!(out, "📝 Manifest changes:\n{diffs}");
+        }
+
+        out
+    }
+}
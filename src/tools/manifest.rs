@@ -0,0 +1,503 @@
+use crate::tools::cargo_utils::{capture_output, create_cargo_command};
+use crate::tools::metadata::Metadata;
+use crate::tools::process::Limits;
+use anyhow::{Result, anyhow};
+use similar::TextDiff;
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    env, fs,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, TableLike, Value};
+
+/// A Cargo.toml parsed for editing, keeping its formatting and comments
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub original: String,
+    pub document: DocumentMut,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let original = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Could not read {}: {e}", path.display()))?;
+        let document = original
+            .parse()
+            .map_err(|e| anyhow!("Could not parse {}: {e}", path.display()))?;
+        Ok(Self { original, document })
+    }
+
+    fn workspace(&mut self) -> Result<&mut Table> {
+        self.document
+            .get_mut("workspace")
+            .and_then(Item::as_table_mut)
+            .ok_or_else(|| anyhow!("The workspace root manifest has no [workspace] table"))
+    }
+
+    /// The `[workspace.dependencies]` table, created if the manifest has a `[workspace]`
+    /// table but no dependencies in it yet
+    pub fn workspace_dependencies(&mut self) -> Result<&mut Table> {
+        self.workspace()?
+            .entry("dependencies")
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("workspace.dependencies is not a table"))
+    }
+
+    /// The `[workspace.lints]` table, created if the manifest has a `[workspace]` table
+    /// but no lints in it yet
+    pub fn workspace_lints(&mut self) -> Result<&mut Table> {
+        implicit_table(self.workspace()?, "lints")
+            .ok_or_else(|| anyhow!("workspace.lints is not a table"))
+    }
+
+    /// The package's `[lints]` table, created if it has none yet
+    pub fn lints(&mut self) -> Result<&mut Table> {
+        implicit_table(self.document.as_table_mut(), "lints")
+            .ok_or_else(|| anyhow!("lints is not a table"))
+    }
+
+    /// Every table that declares dependencies: `[dependencies]`, `[dev-dependencies]` and
+    /// `[build-dependencies]`, their platform-specific versions under `[target]`, and
+    /// `[workspace.dependencies]`
+    pub fn dependency_tables_mut(&mut self) -> Vec<&mut dyn TableLike> {
+        const KINDS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+        let mut tables = vec![];
+        for (key, item) in self.document.iter_mut() {
+            match key.get() {
+                kind if KINDS.contains(&kind) => tables.extend(item.as_table_like_mut()),
+                "workspace" => tables.extend(
+                    item.get_mut("dependencies")
+                        .and_then(Item::as_table_like_mut),
+                ),
+                "target" => {
+                    let Some(platforms) = item.as_table_like_mut() else {
+                        continue;
+                    };
+                    for (_, platform) in platforms.iter_mut() {
+                        let Some(platform) = platform.as_table_like_mut() else {
+                            continue;
+                        };
+                        tables.extend(
+                            platform
+                                .iter_mut()
+                                .filter(|(key, _)| KINDS.contains(&key.get()))
+                                .filter_map(|(_, item)| item.as_table_like_mut()),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        tables
+    }
+
+    /// Set the value at a dotted key such as `profile.release.lto`, or remove it when
+    /// `value` is `None`. `value` is parsed as TOML, falling back to a plain string.
+    ///
+    /// `bin.<name>` and `example.<name>` address the `[[bin]]` or `[[example]]` entry with
+    /// that name; set one to an inline table to add or replace the whole entry.
+    pub fn edit(&mut self, key: &str, value: Option<&str>) -> Result<()> {
+        let segments: Vec<&str> = key.split('.').collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(anyhow!("Invalid key '{key}'"));
+        }
+        let value = value.map(parse_value);
+
+        match segments.as_slice() {
+            [kind @ ("bin" | "example"), name, rest @ ..] => {
+                self.edit_target(kind, name, rest, value, key)
+            }
+            ["features" | "profile" | "package", _, ..] => {
+                edit_table(self.document.as_table_mut(), &segments, value, true, key)
+            }
+            _ => Err(anyhow!(
+                "Can't edit '{key}': keys must be in features, profile, package, bin or \
+                 example (use cargo_add and cargo_remove for dependencies)"
+            )),
+        }
+    }
+
+    fn edit_target(
+        &mut self,
+        kind: &str,
+        name: &str,
+        rest: &[&str],
+        value: Option<Value>,
+        key: &str,
+    ) -> Result<()> {
+        let targets = self
+            .document
+            .entry(kind)
+            .or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()))
+            .as_array_of_tables_mut()
+            .ok_or_else(|| anyhow!("{kind} is not an array of [[{kind}]] tables"))?;
+        let index = targets
+            .iter()
+            .position(|table| table.get("name").and_then(Item::as_str) == Some(name));
+
+        let result = match (index, rest, value) {
+            (Some(index), [], None) => {
+                targets.remove(index);
+                Ok(())
+            }
+            (None, _, None) => Err(anyhow!("There is no [[{kind}]] named '{name}'")),
+            (index, [], Some(Value::InlineTable(fields))) => {
+                let mut table = Table::new();
+                table.insert("name", toml_edit::value(name));
+                for (field, value) in fields.into_table() {
+                    if field != "name" {
+                        table.insert(&field, value);
+                    }
+                }
+                match index {
+                    Some(index) => {
+                        if let Some(existing) = targets.get_mut(index) {
+                            *existing = table;
+                        }
+                    }
+                    None => targets.push(table),
+                }
+                Ok(())
+            }
+            (_, [], Some(_)) => Err(anyhow!(
+                "Set {key} to an inline table of its fields, e.g. {{ path = \"src/bin/{name}.rs\" }}"
+            )),
+            (index, rest, value) => {
+                let index = index.unwrap_or_else(|| {
+                    let mut table = Table::new();
+                    table.insert("name", toml_edit::value(name));
+                    targets.push(table);
+                    targets.len() - 1
+                });
+                let table = targets
+                    .get_mut(index)
+                    .ok_or_else(|| anyhow!("There is no [[{kind}]] named '{name}'"))?;
+                edit_table(table, rest, value, true, key)
+            }
+        };
+
+        if targets.is_empty() {
+            self.document.remove(kind);
+        }
+        result
+    }
+
+    /// Whether editing changed the manifest
+    pub fn is_changed(&self) -> bool {
+        self.document.to_string() != self.original
+    }
+
+    /// A unified diff of the edits, with paths shown as `name`
+    pub fn diff(&self, name: &str) -> String {
+        let edited = self.document.to_string();
+        TextDiff::from_lines(&self.original, &edited)
+            .unified_diff()
+            .header(&format!("a/{name}"), &format!("b/{name}"))
+            .to_string()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.document.to_string())
+            .map_err(|e| anyhow!("Could not write {}: {e}", path.display()))
+    }
+}
+
+/// The table at `key`, added without a header of its own if it doesn't exist yet
+fn implicit_table<'a>(table: &'a mut Table, key: &str) -> Option<&'a mut Table> {
+    table
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+}
+
+/// Have cargo check edited manifests, then write them unless this is a dry run. Cargo reads
+/// them in a [`WorkspaceCopy`], so nothing in the workspace changes until they have been
+/// accepted. Returns any warnings cargo printed.
+pub fn save_checked(
+    manifests: &[(&Path, &Manifest)],
+    metadata: &Metadata,
+    toolchain: Option<&str>,
+    dry_run: bool,
+    limits: &Limits,
+) -> Result<Vec<String>> {
+    let copy = WorkspaceCopy::create(metadata, manifests)?;
+    let mut cmd = create_cargo_command(
+        &["metadata", "--format-version", "1", "--no-deps"],
+        toolchain,
+        None,
+    );
+    let output = capture_output(&mut cmd, &copy.root, limits)?;
+    let stderr = copy.original_paths(&output.stderr);
+    drop(copy);
+
+    if !output.success() {
+        return Err(anyhow!(
+            "cargo rejected the edited manifest, so it was left unchanged:\n{}",
+            stderr.trim()
+        ));
+    }
+    if !dry_run {
+        save_all(manifests)?;
+    }
+
+    Ok(stderr
+        .lines()
+        .filter(|line| line.starts_with("warning:"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Write manifests, putting back the ones already written if one fails so that the
+/// workspace isn't left half edited
+fn save_all(manifests: &[(&Path, &Manifest)]) -> Result<()> {
+    for (index, (path, manifest)) in manifests.iter().enumerate() {
+        let Err(e) = manifest.save(path) else {
+            continue;
+        };
+        let unrestored: Vec<String> = manifests[..index]
+            .iter()
+            .filter(|(path, manifest)| fs::write(path, &manifest.original).is_err())
+            .map(|(path, _)| path.display().to_string())
+            .collect();
+        if unrestored.is_empty() {
+            return Err(e);
+        }
+        return Err(anyhow!(
+            "{e}\nThe manifests written before it could not be restored either and still \
+             have the edits: {}",
+            unrestored.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// A copy of a workspace in a temporary directory, for cargo to check edited manifests
+/// in. Every manifest of the workspace is written out, with the edits, and with the paths
+/// of dependencies that lead out of the workspace made absolute; everything else links
+/// back to the original files. The copy is removed when dropped.
+struct WorkspaceCopy {
+    root: PathBuf,
+    original_root: PathBuf,
+}
+
+impl WorkspaceCopy {
+    fn create(metadata: &Metadata, edited: &[(&Path, &Manifest)]) -> Result<Self> {
+        static COPIES: AtomicU64 = AtomicU64::new(0);
+        let canonicalize = |path: &Path| {
+            fs::canonicalize(path).map_err(|e| anyhow!("Could not read {}: {e}", path.display()))
+        };
+
+        let original_root = canonicalize(&metadata.workspace_root)?;
+        let root = env::temp_dir().join(format!(
+            "cargo-mcp-check-{}-{}",
+            process::id(),
+            COPIES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&root).map_err(|e| anyhow!("Could not create {}: {e}", root.display()))?;
+        let mut copy = Self {
+            root,
+            original_root,
+        };
+        copy.root = canonicalize(&copy.root)?;
+
+        // The directories holding a manifest, relative to the root, with their manifests
+        let mut manifests: BTreeMap<PathBuf, Manifest> = BTreeMap::new();
+        for (path, manifest) in edited {
+            let dir = copy.relative_dir(&canonicalize(path)?)?;
+            manifests.insert(dir, (*manifest).clone());
+        }
+        let root_manifest = metadata.workspace_root.join("Cargo.toml");
+        let members = metadata.members().map(|package| &package.manifest_path);
+        for path in std::iter::once(&root_manifest).chain(members) {
+            let dir = copy.relative_dir(&canonicalize(path)?)?;
+            if let Entry::Vacant(entry) = manifests.entry(dir) {
+                entry.insert(Manifest::load(path)?);
+            }
+        }
+
+        // Directories on the way to a manifest are created; everything else is linked
+        let dirs: BTreeSet<&Path> = manifests.keys().flat_map(|dir| dir.ancestors()).collect();
+        for dir in &dirs {
+            fs::create_dir_all(copy.root.join(dir))?;
+            for entry in fs::read_dir(copy.original_root.join(dir))? {
+                let entry = entry?;
+                let relative = dir.join(entry.file_name());
+                let skip = dirs.contains(relative.as_path())
+                    || (entry.file_name() == "Cargo.toml" && manifests.contains_key(*dir))
+                    || (dir.as_os_str().is_empty()
+                        && matches!(entry.file_name().to_str(), Some("target" | "Cargo.lock")));
+                if !skip {
+                    link(&entry.path(), &copy.root.join(relative))?;
+                }
+            }
+        }
+
+        for (dir, mut manifest) in manifests {
+            let original_dir = copy.original_root.join(&dir);
+            absolutize_outside_paths(&mut manifest, &original_dir, &copy.original_root);
+            manifest.save(&copy.root.join(dir).join("Cargo.toml"))?;
+        }
+
+        Ok(copy)
+    }
+
+    /// The directory of a manifest, relative to the workspace root
+    fn relative_dir(&self, manifest_path: &Path) -> Result<PathBuf> {
+        manifest_path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&self.original_root).ok())
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow!("{} is outside the workspace", manifest_path.display()))
+    }
+
+    /// Cargo's output with paths into the copy pointed back at the workspace
+    fn original_paths(&self, text: &str) -> String {
+        text.replace(
+            &*self.root.to_string_lossy(),
+            &self.original_root.to_string_lossy(),
+        )
+    }
+}
+
+impl Drop for WorkspaceCopy {
+    fn drop(&mut self) {
+        // Links are removed without following them
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(unix)]
+fn link(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn link(original: &Path, link: &Path) -> std::io::Result<()> {
+    if original.is_dir() {
+        std::os::windows::fs::symlink_dir(original, link)
+    } else {
+        std::os::windows::fs::symlink_file(original, link)
+    }
+}
+
+/// Make the paths of dependencies and patches in the manifest at `dir` that lead out of
+/// `root` absolute, so that they still resolve from a copy of the manifest elsewhere
+fn absolutize_outside_paths(manifest: &mut Manifest, dir: &Path, root: &Path) {
+    let absolutize = |item: &mut Item| {
+        let Some(entry) = item.as_table_like_mut() else {
+            return;
+        };
+        let Some(path) = entry.get("path").and_then(Item::as_str) else {
+            return;
+        };
+        let resolved = normalize(&dir.join(path));
+        if !resolved.starts_with(root) {
+            entry.insert(
+                "path",
+                toml_edit::value(resolved.to_string_lossy().as_ref()),
+            );
+        }
+    };
+
+    for table in manifest.dependency_tables_mut() {
+        for (_, item) in table.iter_mut() {
+            absolutize(item);
+        }
+    }
+    if let Some(patches) = manifest
+        .document
+        .get_mut("patch")
+        .and_then(Item::as_table_like_mut)
+    {
+        for (_, registry) in patches.iter_mut() {
+            if let Some(registry) = registry.as_table_like_mut() {
+                for (_, item) in registry.iter_mut() {
+                    absolutize(item);
+                }
+            }
+        }
+    }
+}
+
+/// Resolve `.` and `..` in a path without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Parse a value written as TOML, such as `true`, `"fat"` or `["std"]`; anything that
+/// isn't valid TOML is taken as a string
+fn parse_value(text: &str) -> Value {
+    text.parse()
+        .unwrap_or_else(|_| Value::from(text.to_string()))
+}
+
+/// Set or remove the value at `path` below `table`, creating tables along the way when
+/// setting. `standard` says whether `table` is written with a `[header]` rather than
+/// inline, in which case an inline table value is given its own header too.
+fn edit_table(
+    table: &mut dyn TableLike,
+    path: &[&str],
+    value: Option<Value>,
+    standard: bool,
+    key: &str,
+) -> Result<()> {
+    let Some((last, parents)) = path.split_last() else {
+        return Err(anyhow!("Invalid key '{key}'"));
+    };
+
+    if let Some((first, rest)) = parents.split_first() {
+        let item = match value {
+            Some(_) => table.entry(first).or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            }),
+            None => table
+                .get_mut(first)
+                .ok_or_else(|| anyhow!("{key} is not set"))?,
+        };
+        let standard = item.is_table();
+        let child = item
+            .as_table_like_mut()
+            .ok_or_else(|| anyhow!("Can't edit {key}: {first} is not a table"))?;
+        let mut path = rest.to_vec();
+        path.push(last);
+        return edit_table(child, &path, value, standard, key);
+    }
+
+    match value {
+        None => table
+            .remove(last)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("{key} is not set")),
+        Some(Value::InlineTable(inline)) if standard => {
+            table.insert(last, Item::Table(inline.into_table()));
+            Ok(())
+        }
+        Some(mut value) => {
+            // Keep any comment attached to the value being replaced
+            if let Some(existing) = table.get(last).and_then(Item::as_value) {
+                *value.decor_mut() = existing.decor().clone();
+            }
+            table.insert(last, Item::Value(value));
+            Ok(())
+        }
+    }
+}
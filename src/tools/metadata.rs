@@ -0,0 +1,282 @@
+use crate::tools::cargo_utils::{capture_output, create_cargo_command};
+use crate::tools::process::Limits;
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// The parts of `cargo metadata --format-version 1` that the tools use
+#[derive(Debug, Clone, Deserialize)]
+pub struct Metadata {
+    pub packages: Vec<Package>,
+    pub workspace_members: Vec<String>,
+    pub workspace_root: PathBuf,
+    pub target_directory: PathBuf,
+    /// The resolved dependency graph; absent with `--no-deps`
+    pub resolve: Option<Resolve>,
+}
+
+/// A package in the workspace or its dependency graph
+#[derive(Debug, Clone, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub id: String,
+    pub manifest_path: PathBuf,
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// The package's minimum supported Rust version (`rust-version`)
+    pub rust_version: Option<String>,
+    /// Registries the package may be published to; empty with `publish = false`, and
+    /// absent if it may be published anywhere
+    pub publish: Option<Vec<String>>,
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A compilation target of a package: its library, a binary, an example, ...
+#[derive(Debug, Clone, Deserialize)]
+pub struct Target {
+    pub name: String,
+    /// lib, bin, example, test, bench, custom-build, or a library crate type
+    pub kind: Vec<String>,
+    pub src_path: PathBuf,
+    #[serde(default, rename = "required-features")]
+    pub required_features: Vec<String>,
+}
+
+/// A dependency as declared in a package's manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct Dependency {
+    /// Name of the package depended on
+    pub name: String,
+    /// Version requirement
+    pub req: String,
+    /// `dev` or `build`; normal dependencies have none
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub optional: bool,
+    /// Name the dependency is imported as, if renamed
+    pub rename: Option<String>,
+    /// Platform the dependency is limited to, e.g. `cfg(unix)`
+    pub target: Option<String>,
+}
+
+/// The resolved dependency graph
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resolve {
+    pub nodes: Vec<Node>,
+}
+
+/// A package in the resolved graph and the packages it depends on
+#[derive(Debug, Clone, Deserialize)]
+pub struct Node {
+    pub id: String,
+    #[serde(default)]
+    pub deps: Vec<NodeDep>,
+}
+
+/// A resolved dependency edge
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeDep {
+    /// Id of the package depended on
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<DepKind>,
+}
+
+/// One way a resolved dependency is used; a package can be both a normal and a build
+/// dependency
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepKind {
+    /// `dev` or `build`; normal dependencies have none
+    pub kind: Option<String>,
+}
+
+/// Run `cargo metadata` in the project directory. With `no_deps`, only workspace members
+/// are listed, which is much faster and doesn't need the dependencies to be resolved.
+pub fn load_metadata(
+    project_path: &Path,
+    toolchain: Option<&str>,
+    no_deps: bool,
+    limits: &Limits,
+) -> Result<Metadata> {
+    let mut args = vec!["metadata", "--format-version", "1"];
+    if no_deps {
+        args.push("--no-deps");
+    }
+
+    let mut cmd = create_cargo_command(&args, toolchain, None);
+    let output = capture_output(&mut cmd, project_path, limits)?;
+    if !output.success() {
+        return Err(anyhow!("cargo metadata failed: {}", output.stderr.trim()));
+    }
+
+    serde_json::from_str(&output.stdout)
+        .map_err(|e| anyhow!("Could not parse cargo metadata output: {e}"))
+}
+
+impl Target {
+    /// Whether the target is of this kind. Libraries are reported by crate type, so any
+    /// of lib, rlib, dylib, cdylib, staticlib and proc-macro count as `lib`.
+    pub fn is(&self, kind: &str) -> bool {
+        self.kind.iter().any(|k| {
+            k == kind
+                || (kind == "lib"
+                    && matches!(
+                        k.as_str(),
+                        "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro"
+                    ))
+        })
+    }
+}
+
+impl Metadata {
+    pub fn package(&self, id: &str) -> Option<&Package> {
+        self.packages.iter().find(|package| package.id == id)
+    }
+
+    /// Packages that `package` depends on in the resolved graph
+    pub fn resolved_deps(&self, package: &Package) -> Vec<&Package> {
+        let Some(resolve) = &self.resolve else {
+            return vec![];
+        };
+        resolve
+            .nodes
+            .iter()
+            .find(|node| node.id == package.id)
+            .map(|node| {
+                node.deps
+                    .iter()
+                    .filter_map(|dep| self.package(&dep.pkg))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Packages matching a spec of the form `name` or `name@version`
+    pub fn find_packages(&self, spec: &str) -> Result<Vec<&Package>> {
+        let (name, version) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec, None),
+        };
+        let packages: Vec<&Package> = self
+            .packages
+            .iter()
+            .filter(|package| {
+                package.name == name
+                    && version.is_none_or(|version| {
+                        // `syn@1` matches 1.0.109 but not 10.0.0
+                        let mut components = package.version.split('.');
+                        version
+                            .split('.')
+                            .all(|component| components.next() == Some(component))
+                    })
+            })
+            .collect();
+        if packages.is_empty() {
+            return Err(anyhow!(
+                "No package matching '{spec}' in the dependency graph"
+            ));
+        }
+        Ok(packages)
+    }
+
+    /// Workspace members, in the order cargo lists them
+    pub fn members(&self) -> impl Iterator<Item = &Package> {
+        self.packages
+            .iter()
+            .filter(|package| self.is_member(&package.id))
+    }
+
+    pub fn is_member(&self, id: &str) -> bool {
+        self.workspace_members.iter().any(|member| member == id)
+    }
+
+    /// The workspace member with the given name
+    pub fn member(&self, name: &str) -> Result<&Package> {
+        self.members()
+            .find(|package| package.name == name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No workspace member named '{name}'. Workspace members: {}",
+                    self.member_names()
+                )
+            })
+    }
+
+    fn member_names(&self) -> String {
+        self.members()
+            .map(|package| package.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The workspace member with the given name, or else the one whose manifest is in the
+    /// project directory, or else the only member
+    pub fn select_member(&self, name: Option<&str>, project_path: &Path) -> Result<&Package> {
+        if let Some(name) = name {
+            return self.member(name);
+        }
+
+        let manifest = project_path.join("Cargo.toml").canonicalize().ok();
+        if let Some(package) = self
+            .members()
+            .find(|package| Some(&package.manifest_path) == manifest.as_ref())
+        {
+            return Ok(package);
+        }
+
+        let mut members = self.members();
+        match (members.next(), members.next()) {
+            (Some(package), None) => Ok(package),
+            _ => Err(anyhow!(
+                "This is a workspace; pass package to pick one of: {}",
+                self.member_names()
+            )),
+        }
+    }
+
+    /// Check that `cargo run` can find the binary or example it is asked to run, so that a
+    /// typo is reported with the names that do exist
+    pub fn check_run_target(
+        &self,
+        package: Option<&str>,
+        bin: Option<&str>,
+        example: Option<&str>,
+    ) -> Result<()> {
+        let candidates: Vec<&Package> = match package {
+            Some(name) => vec![self.member(name)?],
+            None => self.members().collect(),
+        };
+
+        for (kind, label, name) in [("bin", "binary", bin), ("example", "example", example)] {
+            let Some(name) = name else {
+                continue;
+            };
+            let available: Vec<&str> = candidates
+                .iter()
+                .flat_map(|package| &package.targets)
+                .filter(|target| target.is(kind))
+                .map(|target| target.name.as_str())
+                .collect();
+            if !available.contains(&name) {
+                let place = package.map_or("the workspace".to_string(), |p| format!("'{p}'"));
+                return Err(if available.is_empty() {
+                    anyhow!("No {label} named '{name}': {place} has no {label} targets")
+                } else {
+                    anyhow!(
+                        "No {label} named '{name}' in {place}. Available: {}",
+                        available.join(", ")
+                    )
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+use crate::tools::diagnostics::plural;
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Output budget used when none is configured, in bytes of combined stdout and stderr
+pub const DEFAULT_OUTPUT_BUDGET: usize = 20_000;
+
+/// Maximum number of lines kept from a single error block when truncating
+const MAX_ERROR_BLOCK_LINES: usize = 40;
+
+/// Cargo status lines that only report progress
+const PROGRESS_PREFIXES: &[&str] = &[
+    "Compiling ",
+    "Checking ",
+    "Downloaded ",
+    "Downloading ",
+    "Documenting ",
+    "Fresh ",
+    "Blocking waiting for file lock",
+];
+
+/// Remove ANSI escape sequences (colors, cursor movement, hyperlinks)
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters and intermediates, terminated by a byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Two-character sequences
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Replace cargo's progress lines (`Compiling foo v1.0.0`, ...) with a single line counting
+/// them. The summary takes the place of the first progress line.
+pub fn collapse_progress(text: &str) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut summary_at = None;
+    let mut kept = vec![];
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match PROGRESS_PREFIXES
+            .iter()
+            .find(|prefix| trimmed.starts_with(*prefix))
+        {
+            Some(prefix) => {
+                *counts.entry(prefix.trim_end()).or_default() += 1;
+                summary_at.get_or_insert(kept.len());
+            }
+            None => kept.push(line),
+        }
+    }
+
+    let Some(summary_at) = summary_at else {
+        return text.to_string();
+    };
+
+    let total: usize = counts.values().sum();
+    let breakdown = counts
+        .iter()
+        .map(|(kind, count)| format!("{count} {kind}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let summary = format!(
+        "[{} collapsed: {breakdown}]",
+        plural(total, "progress line", "progress lines")
+    );
+
+    kept.insert(summary_at, &summary);
+    let mut out = kept.join("\n");
+    if text.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Information about output that was elided to fit the budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub total_lines: usize,
+    pub kept_lines: usize,
+    pub total_bytes: usize,
+}
+
+/// Lines chosen to be kept, and how much of the budget they leave
+struct Selection {
+    keep: Vec<bool>,
+    remaining: usize,
+}
+
+impl Selection {
+    /// Keep a line if it fits in the remaining budget
+    fn take(&mut self, index: usize, line: &str) -> bool {
+        if self.keep[index] {
+            return true;
+        }
+        let cost = line.len() + 1;
+        if cost > self.remaining {
+            return false;
+        }
+        self.keep[index] = true;
+        self.remaining -= cost;
+        true
+    }
+}
+
+fn is_error_start(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("error:")
+        || line.starts_with("error[")
+        || line.contains("panicked at")
+        || line.starts_with("---- ")
+}
+
+/// Shrink text to roughly `budget` bytes, keeping the head, the tail, and every error
+/// block in between (up to the budget). Elided stretches are replaced with a marker line.
+///
+/// A budget of zero means unlimited.
+pub fn truncate_to_budget(text: &str, budget: usize) -> (String, Option<Truncation>) {
+    if budget == 0 || text.len() <= budget {
+        return (text.to_string(), None);
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut selection = Selection {
+        keep: vec![false; lines.len()],
+        remaining: budget,
+    };
+
+    // A quarter of the budget each for the head and the tail
+    let mut head_budget = budget / 4;
+    for (index, line) in lines.iter().enumerate() {
+        if line.len() + 1 > head_budget || !selection.take(index, line) {
+            break;
+        }
+        head_budget -= line.len() + 1;
+    }
+
+    let mut tail_budget = budget / 4;
+    for (index, line) in lines.iter().enumerate().rev() {
+        if selection.keep[index] || line.len() + 1 > tail_budget || !selection.take(index, line) {
+            break;
+        }
+        tail_budget -= line.len() + 1;
+    }
+
+    // Whatever is left goes to error blocks, in order of appearance
+    let mut index = 0;
+    while index < lines.len() {
+        if selection.keep[index] || !is_error_start(lines[index]) {
+            index += 1;
+            continue;
+        }
+
+        let block_end = (index + 1..lines.len())
+            .take(MAX_ERROR_BLOCK_LINES - 1)
+            .find(|&i| lines[i].trim().is_empty())
+            .unwrap_or_else(|| (index + MAX_ERROR_BLOCK_LINES).min(lines.len()));
+
+        for (line_index, line) in lines.iter().enumerate().take(block_end).skip(index) {
+            if !selection.take(line_index, line) {
+                break;
+            }
+        }
+        index = block_end;
+    }
+
+    let keep = selection.keep;
+    let mut out = String::with_capacity(budget + 100);
+    let mut elided = 0;
+    for (line, kept) in lines.iter().zip(&keep) {
+        if *kept {
+            if elided > 0 {
+                let _ = writeln!(out, "[… {elided} lines elided …]");
+                elided = 0;
+            }
+            out.push_str(line);
+            out.push('\n');
+        } else {
+            elided += 1;
+        }
+    }
+    if elided > 0 {
+        let _ = writeln!(out, "[… {elided} lines elided …]");
+    }
+
+    let truncation = Truncation {
+        total_lines: lines.len(),
+        kept_lines: keep.iter().filter(|kept| **kept).count(),
+        total_bytes: text.len(),
+    };
+
+    (out, Some(truncation))
+}
+
+/// Split an output budget between stdout and stderr. A stream that needs less than half
+/// of the budget leaves the remainder to the other.
+pub fn split_budget(budget: usize, stdout_len: usize, stderr_len: usize) -> (usize, usize) {
+    if budget == 0 {
+        return (0, 0);
+    }
+
+    let half = budget / 2;
+    if stdout_len <= half {
+        (half, budget - stdout_len)
+    } else if stderr_len <= half {
+        (budget - stderr_len, half)
+    } else {
+        (half, budget - half)
+    }
+}
+
+/// Strip ANSI escapes and collapse progress lines
+pub fn denoise(text: &str) -> String {
+    collapse_progress(&strip_ansi(text))
+}
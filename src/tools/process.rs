@@ -0,0 +1,407 @@
+use crate::command_log::Interruption;
+use anyhow::Result;
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How often a running process is checked for exit, timeout and cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a process group gets to exit after SIGTERM before it is sent SIGKILL
+const TERMINATE_GRACE: Duration = Duration::from_secs(2);
+
+/// How long to keep waiting for output once the command has exited. Descendants it left
+/// running can hold the pipes open indefinitely.
+const DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+/// Process groups of the commands that are running, so they can be stopped if the server
+/// is told to exit
+static RUNNING: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// The captured result of running a command
+#[derive(Debug)]
+pub struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set if the command was killed before it finished; stdout and stderr hold whatever
+    /// it had written until then
+    pub interruption: Option<Interruption>,
+    /// Id of the `cargo_log` entry holding the full output, if it was logged
+    pub run_id: Option<u64>,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.interruption.is_none() && self.status.success()
+    }
+}
+
+/// Conditions under which a running command is killed
+#[derive(Debug, Default, Clone)]
+pub struct Limits {
+    /// Kill the command after this long
+    pub timeout: Option<Duration>,
+    /// Kill the command once this is set
+    pub cancelled: Arc<AtomicBool>,
+}
+
+/// How a process's output is kept while it runs
+#[derive(Debug, Default)]
+pub struct Capture {
+    /// Only keep about the last this many bytes of each stream in memory
+    pub limit: Option<usize>,
+    /// Files that stdout and stderr are also written to in full
+    pub files: Option<(File, File)>,
+}
+
+/// Output of one stream captured so far. With a limit, only the last `limit` to
+/// `2 * limit` bytes are kept, cut at a line boundary; offsets count from the start of the
+/// stream either way.
+#[derive(Debug, Default)]
+pub struct Captured {
+    bytes: Vec<u8>,
+    limit: Option<usize>,
+    /// Bytes dropped from the front to stay within the limit
+    dropped: usize,
+    /// Complete lines dropped from the front
+    dropped_lines: usize,
+}
+
+impl Captured {
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+        let Some(limit) = self.limit else {
+            return;
+        };
+        if self.bytes.len() <= 2 * limit {
+            return;
+        }
+
+        let excess = self.bytes.len() - limit;
+        let cut = self.bytes[excess..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(excess, |newline| excess + newline + 1);
+        self.dropped_lines += self.bytes[..cut].iter().filter(|b| **b == b'\n').count();
+        self.dropped += cut;
+        self.bytes.drain(..cut);
+    }
+
+    /// The output that is still held
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+
+    /// Number of complete lines that were dropped to stay within the limit
+    pub fn dropped_lines(&self) -> usize {
+        self.dropped_lines
+    }
+
+    /// Number of lines written so far, including dropped ones
+    pub fn line_count(&self) -> usize {
+        let held = self.bytes.iter().filter(|byte| **byte == b'\n').count();
+        let unterminated = self.bytes.last().is_some_and(|byte| *byte != b'\n');
+        self.dropped_lines + held + usize::from(unterminated)
+    }
+
+    /// Complete lines (ended by a newline or carriage return) written since the byte
+    /// offset `read`, advancing `read` past them. Lines that were dropped are skipped.
+    pub fn lines_since(&self, read: &mut usize) -> Vec<String> {
+        let start = read.saturating_sub(self.dropped);
+        *read = (*read).max(self.dropped);
+        let new = &self.bytes[start..];
+        let Some(end) = new.iter().rposition(|byte| matches!(byte, b'\n' | b'\r')) else {
+            return vec![];
+        };
+        *read += end + 1;
+        String::from_utf8_lossy(&new[..end])
+            .split(['\n', '\r'])
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Output of one stream, shared with the thread that reads it
+pub type OutputBuffer = Arc<Mutex<Captured>>;
+
+/// Output of one stream, collected on its own thread so that partial output is available
+/// while the process runs, or if it has to be killed
+struct StreamReader {
+    buffer: OutputBuffer,
+    handle: JoinHandle<()>,
+}
+
+impl StreamReader {
+    fn spawn(
+        mut stream: impl Read + Send + 'static,
+        limit: Option<usize>,
+        mut file: Option<File>,
+    ) -> Self {
+        let buffer = Arc::new(Mutex::new(Captured {
+            limit,
+            ..Captured::default()
+        }));
+        let thread_buffer = Arc::clone(&buffer);
+        let handle = thread::spawn(move || {
+            let mut chunk = [0; 8192];
+            while let Ok(read @ 1..) = stream.read(&mut chunk) {
+                if let Some(output) = &mut file
+                    && let Err(e) = output.write_all(&chunk[..read])
+                {
+                    log::warn!("Could not log output: {e}");
+                    file = None;
+                }
+                thread_buffer.lock().unwrap().push(&chunk[..read]);
+            }
+        });
+        Self { buffer, handle }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    fn contents(&self) -> String {
+        self.buffer.lock().unwrap().text()
+    }
+}
+
+/// A command running in its own process group, with its output being captured
+pub struct Process {
+    child: Child,
+    stdout: StreamReader,
+    stderr: StreamReader,
+    started: Instant,
+}
+
+impl Process {
+    /// Start a command in the project directory in its own process group. `input` is
+    /// written to its stdin, which is then closed; without input, stdin is connected to
+    /// /dev/null so the command can never read from the server's own stdin.
+    pub fn spawn(cmd: &mut Command, project_path: &Path, input: Option<Vec<u8>>) -> Result<Self> {
+        Self::spawn_with(cmd, project_path, input, Capture::default())
+    }
+
+    /// Like [`Process::spawn`], keeping its output as `capture` says
+    pub fn spawn_with(
+        cmd: &mut Command,
+        project_path: &Path,
+        input: Option<Vec<u8>>,
+        capture: Capture,
+    ) -> Result<Self> {
+        let stdin = if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        cmd.current_dir(project_path)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        // If the server is killed before it can stop the command, don't let it run on
+        // unattended
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::process::CommandExt;
+            let server = std::process::id();
+            // Safety: prctl and getppid are async-signal-safe and touch no memory
+            unsafe {
+                cmd.pre_exec(move || {
+                    libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                    if u32::try_from(libc::getppid()) != Ok(server) {
+                        return Err(std::io::Error::other("the server has exited"));
+                    }
+                    Ok(())
+                });
+            }
+        }
+
+        let started = Instant::now();
+        let mut child = cmd.spawn()?;
+        RUNNING.lock().unwrap().insert(child.id());
+        if let Some(input) = input {
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            // Written on its own thread, as a command that doesn't read all of its input
+            // would otherwise block us once the pipe is full. Errors mean the command
+            // exited or closed stdin early, which is up to it.
+            thread::spawn(move || {
+                let _ = stdin.write_all(&input);
+            });
+        }
+        let (stdout_file, stderr_file) = capture.files.unzip();
+        let stdout = StreamReader::spawn(
+            child.stdout.take().expect("stdout is piped"),
+            capture.limit,
+            stdout_file,
+        );
+        let stderr = StreamReader::spawn(
+            child.stderr.take().expect("stderr is piped"),
+            capture.limit,
+            stderr_file,
+        );
+
+        Ok(Self {
+            child,
+            stdout,
+            stderr,
+            started,
+        })
+    }
+
+    /// Process id of the command, which is also its process group id
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Output captured so far, for reading while the process runs
+    pub fn buffers(&self) -> (OutputBuffer, OutputBuffer) {
+        (
+            Arc::clone(&self.stdout.buffer),
+            Arc::clone(&self.stderr.buffer),
+        )
+    }
+
+    /// Wait for the process to exit and its output to be read. If the timeout expires or
+    /// the command is cancelled first, the whole process group is terminated and the
+    /// output written so far is returned.
+    pub fn wait(self, limits: &Limits) -> Result<CommandOutput> {
+        self.wait_with(limits, || {})
+    }
+
+    /// Like [`Process::wait`], calling `on_poll` every time the process is checked on, for
+    /// example to follow its output through [`Process::buffers`]
+    pub fn wait_with(
+        mut self,
+        limits: &Limits,
+        mut on_poll: impl FnMut(),
+    ) -> Result<CommandOutput> {
+        let mut interruption = None;
+        let mut exited_at = None;
+        let status = loop {
+            on_poll();
+            if let Some(status) = self.child.try_wait()? {
+                // Once the command itself has exited, the limits no longer apply: whatever
+                // still holds the pipes only gets a short while to finish writing
+                let exited_at = *exited_at.get_or_insert_with(Instant::now);
+                let drained = self.stdout.is_finished() && self.stderr.is_finished();
+                if drained || exited_at.elapsed() >= DRAIN_GRACE {
+                    break status;
+                }
+            } else if interruption.is_none() {
+                if limits.cancelled.load(Ordering::SeqCst) {
+                    interruption = Some(Interruption::Cancelled);
+                } else if let Some(timeout) = limits.timeout
+                    && self.started.elapsed() >= timeout
+                {
+                    interruption = Some(Interruption::TimedOut {
+                        after_secs: timeout.as_secs(),
+                    });
+                }
+
+                if interruption.is_some() {
+                    terminate(&mut self.child);
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        Ok(CommandOutput {
+            status,
+            stdout: self.stdout.contents(),
+            stderr: self.stderr.contents(),
+            interruption,
+            run_id: None,
+        })
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        RUNNING.lock().unwrap().remove(&self.child.id());
+    }
+}
+
+/// Stop every command that is still running, as if each was cancelled, for when the
+/// server is told to exit
+#[cfg(unix)]
+pub fn terminate_all() {
+    let groups: Vec<i32> = RUNNING
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|pgid| i32::try_from(*pgid).ok())
+        .collect();
+    for pgid in &groups {
+        // Safety: kill has no memory safety requirements; a negative pid signals the group
+        unsafe { libc::kill(-pgid, libc::SIGTERM) };
+    }
+
+    // The threads waiting on the commands reap them and drop them from RUNNING
+    let deadline = Instant::now() + TERMINATE_GRACE;
+    while Instant::now() < deadline && !RUNNING.lock().unwrap().is_empty() {
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    for pgid in groups {
+        unsafe { libc::kill(-pgid, libc::SIGKILL) };
+    }
+}
+
+/// Run a command in the project directory in its own process group and capture its
+/// output, subject to the given limits
+pub fn run_process(
+    cmd: &mut Command,
+    project_path: &Path,
+    input: Option<Vec<u8>>,
+    limits: &Limits,
+) -> Result<CommandOutput> {
+    Process::spawn(cmd, project_path, input)?.wait(limits)
+}
+
+/// Ask the child's process group to exit, then kill it if it hasn't after a grace period
+#[cfg(unix)]
+fn terminate(child: &mut Child) {
+    let Ok(pgid) = i32::try_from(child.id()) else {
+        let _ = child.kill();
+        return;
+    };
+
+    // Safety: kill has no memory safety requirements; a negative pid signals the group
+    unsafe { libc::kill(-pgid, libc::SIGTERM) };
+
+    let deadline = Instant::now() + TERMINATE_GRACE;
+    while Instant::now() < deadline {
+        if matches!(child.try_wait(), Ok(Some(_))) {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // Even if the child has exited, other members of the group may still be running
+    unsafe { libc::kill(-pgid, libc::SIGKILL) };
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut Child) {
+    let _ = child.kill();
+}
@@ -0,0 +1,225 @@
+use crate::server::ProgressReporter;
+use crate::tools::process::OutputBuffer;
+use serde_json::Value;
+use std::process::Command;
+
+/// Width of cargo's progress bar when it is forced on. Wide enough that the names of the
+/// crates being built are rarely cut off.
+const PROGRESS_BAR_WIDTH: &str = "160";
+
+/// Make cargo draw its progress bar (`Building [===>  ] 12/57: serde`) even though its
+/// stderr is not a terminal, so that the number of units left to build is known
+pub fn enable_progress_bar(cmd: &mut Command) {
+    cmd.env("CARGO_TERM_PROGRESS_WHEN", "always")
+        .env("CARGO_TERM_PROGRESS_WIDTH", PROGRESS_BAR_WIDTH);
+}
+
+/// Remove the frames of cargo's progress bar from its stderr. Each frame is terminated by
+/// a carriage return rather than a newline, so it shares a line with whatever is printed
+/// after it. Other carriage returns, such as the CRLF line endings of a program's output,
+/// are kept.
+pub fn strip_progress_bar(stderr: &str) -> String {
+    let mut out = String::with_capacity(stderr.len());
+    for segment in stderr.split_inclusive(['\r', '\n']) {
+        if segment.ends_with('\n') || parse_progress_bar(segment).is_none() {
+            out.push_str(segment);
+        }
+    }
+    out
+}
+
+/// Parse a progress bar frame into the number of units built, the total, and the units
+/// being built
+fn parse_progress_bar(segment: &str) -> Option<(u64, u64, &str)> {
+    let rest = segment.trim_start().strip_prefix("Building [")?;
+    let (_, rest) = rest.split_once(']')?;
+    let (counts, current) = rest
+        .trim_start()
+        .split_once(':')
+        .unwrap_or((rest.trim(), ""));
+    let (done, total) = counts.trim().split_once('/')?;
+    Some((done.parse().ok()?, total.parse().ok()?, current.trim()))
+}
+
+/// Follows the output of a running cargo command and reports what it is doing: how many
+/// crates have been compiled, and how many tests have passed or failed so far
+pub struct ProgressTracker {
+    reporter: ProgressReporter,
+    stdout: OutputBuffer,
+    stderr: OutputBuffer,
+    stdout_read: usize,
+    stderr_read: usize,
+    /// Units built and the total, from the progress bar
+    building: Option<(u64, u64)>,
+    /// Crates being compiled right now, from the progress bar or status lines
+    current: String,
+    /// `compiler-artifact` messages seen, for commands with JSON output
+    artifacts: u64,
+    /// `Compiling`/`Checking` status lines seen
+    compiled: u64,
+    tests_total: u64,
+    tests_passed: u64,
+    tests_failed: u64,
+    tests_ignored: u64,
+    testing: bool,
+    /// Progress reported in the last notification
+    last_sent: Option<u64>,
+}
+
+impl ProgressTracker {
+    pub fn new(reporter: ProgressReporter, (stdout, stderr): (OutputBuffer, OutputBuffer)) -> Self {
+        Self {
+            reporter,
+            stdout,
+            stderr,
+            stdout_read: 0,
+            stderr_read: 0,
+            building: None,
+            current: String::new(),
+            artifacts: 0,
+            compiled: 0,
+            tests_total: 0,
+            tests_passed: 0,
+            tests_failed: 0,
+            tests_ignored: 0,
+            testing: false,
+            last_sent: None,
+        }
+    }
+
+    /// Read any output written since the last call and send a notification if the
+    /// command has made progress
+    pub fn update(&mut self) {
+        let stderr = self
+            .stderr
+            .lock()
+            .unwrap()
+            .lines_since(&mut self.stderr_read);
+        for line in stderr {
+            self.stderr_line(&line);
+        }
+        let stdout = self
+            .stdout
+            .lock()
+            .unwrap()
+            .lines_since(&mut self.stdout_read);
+        for line in stdout {
+            self.stdout_line(&line);
+        }
+
+        let (progress, total) = self.progress();
+        if self.last_sent.is_some_and(|sent| sent >= progress) {
+            return;
+        }
+        if let Some(message) = self.message() {
+            self.reporter.notify(progress, total, &message);
+            self.last_sent = Some(progress);
+        }
+    }
+
+    fn stderr_line(&mut self, line: &str) {
+        if let Some((done, total, current)) = parse_progress_bar(line) {
+            self.building = Some((done, total));
+            if !current.is_empty() {
+                self.current = current.to_string();
+            }
+            return;
+        }
+
+        let line = line.trim_start();
+        if let Some(krate) = ["Compiling ", "Checking "]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        {
+            self.compiled += 1;
+            self.current = krate.split(' ').next().unwrap_or(krate).to_string();
+        } else if line.starts_with("Running ") || line.starts_with("Doc-tests ") {
+            self.testing = true;
+        }
+    }
+
+    fn stdout_line(&mut self, line: &str) {
+        if line.starts_with('{') {
+            let Ok(message) = serde_json::from_str::<Value>(line) else {
+                return;
+            };
+            match message.get("reason").and_then(Value::as_str) {
+                Some("compiler-artifact") => self.artifacts += 1,
+                _ => match (
+                    message.get("type").and_then(Value::as_str),
+                    message.get("event").and_then(Value::as_str),
+                ) {
+                    (Some("suite"), Some("started")) => {
+                        self.testing = true;
+                        self.tests_total += message
+                            .get("test_count")
+                            .and_then(Value::as_u64)
+                            .unwrap_or(0);
+                    }
+                    (Some("test"), Some(event)) => self.test_event(event),
+                    _ => {}
+                },
+            }
+            return;
+        }
+
+        if let Some(count) = line
+            .strip_prefix("running ")
+            .and_then(|rest| rest.split(' ').next())
+            .and_then(|count| count.parse::<u64>().ok())
+        {
+            self.testing = true;
+            self.tests_total += count;
+        } else if let Some(rest) = line.strip_prefix("test ")
+            && let Some((_, result)) = rest.rsplit_once(" ... ")
+        {
+            self.test_event(result.split(' ').next().unwrap_or(result));
+        }
+    }
+
+    fn test_event(&mut self, event: &str) {
+        match event {
+            "ok" => self.tests_passed += 1,
+            "FAILED" | "failed" => self.tests_failed += 1,
+            "ignored" => self.tests_ignored += 1,
+            _ => {}
+        }
+    }
+
+    /// Progress through building and then testing. Both counts only ever grow, so their
+    /// sum increases as the MCP spec requires.
+    fn progress(&self) -> (u64, Option<u64>) {
+        let tests_done = self.tests_passed + self.tests_failed + self.tests_ignored;
+        let built = self
+            .building
+            .map_or(self.artifacts.max(self.compiled), |(done, _)| done);
+        let total = self.building.map(|(_, total)| total + self.tests_total);
+        (built + tests_done, total)
+    }
+
+    fn message(&self) -> Option<String> {
+        if self.testing {
+            let mut message = format!(
+                "Testing: {} passed, {} failed",
+                self.tests_passed, self.tests_failed
+            );
+            if self.tests_ignored > 0 {
+                message.push_str(&format!(", {} ignored", self.tests_ignored));
+            }
+            if self.tests_total > 0 {
+                message.push_str(&format!(" of {}", self.tests_total));
+            }
+            return Some(message);
+        }
+
+        match self.building {
+            Some((done, total)) => Some(format!("Compiling {done}/{total}: {}", self.current)),
+            None if self.compiled > 0 => Some(format!(
+                "Compiling {} ({} so far)",
+                self.current, self.compiled
+            )),
+            None if self.artifacts > 0 => Some(format!("Compiled {} crates", self.artifacts)),
+            None => None,
+        }
+    }
+}
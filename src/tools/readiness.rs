@@ -0,0 +1,89 @@
+use crate::command_log::LogStream;
+use crate::jobs::Job;
+use crate::tools::output::strip_ansi;
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use std::{
+    net::{Ipv4Addr, SocketAddr, TcpStream},
+    time::Duration,
+};
+
+/// When a detached instance counts as ready
+pub enum Readiness {
+    /// Started, with no condition to wait for
+    Started,
+    Pattern(Regex),
+    Port(u16),
+}
+
+impl Readiness {
+    /// The condition given by cargo_run's ready_pattern and ready_port
+    pub fn new(pattern: Option<&str>, port: Option<u16>) -> Result<Self> {
+        match (pattern, port) {
+            (Some(_), Some(_)) => Err(anyhow!("Pass either ready_pattern or ready_port, not both")),
+            (Some(pattern), None) => Ok(Self::Pattern(
+                Regex::new(pattern).map_err(|e| anyhow!("Invalid ready_pattern: {e}"))?,
+            )),
+            (None, Some(port)) => {
+                // Something else answering on the port would make the instance look ready
+                if port_is_open(port) {
+                    return Err(anyhow!("Port {port} is already accepting connections"));
+                }
+                Ok(Self::Port(port))
+            }
+            (None, None) => Ok(Self::Started),
+        }
+    }
+
+    /// Describes why the instance is ready, given the lines its program wrote since the
+    /// last check, or None if it isn't yet
+    pub fn check(&self, lines: Vec<String>) -> Option<String> {
+        match self {
+            Self::Started => Some("started".to_string()),
+            Self::Pattern(regex) => lines
+                .into_iter()
+                .find(|line| regex.is_match(line))
+                .map(|line| format!("output matched: {}", line.trim())),
+            Self::Port(port) => {
+                port_is_open(*port).then(|| format!("localhost:{port} is accepting connections"))
+            }
+        }
+    }
+}
+
+/// Follows the lines a detached instance's program writes: all of stdout, and stderr
+/// after cargo's `Running` line, so that cargo's own messages can't match a ready pattern
+#[derive(Default)]
+pub struct ProgramOutput {
+    stdout_read: usize,
+    stderr_read: usize,
+    /// Whether cargo has started the program
+    pub started: bool,
+}
+
+impl ProgramOutput {
+    /// Lines the program has written since the last call
+    pub fn read(&mut self, job: &Job) -> Vec<String> {
+        let stdout = job.lines_since(LogStream::Stdout, &mut self.stdout_read);
+        let stderr = job.lines_since(LogStream::Stderr, &mut self.stderr_read);
+        self.follow(stdout, stderr)
+    }
+
+    /// The program's lines among new lines of the job's stdout and stderr
+    pub fn follow(&mut self, mut stdout: Vec<String>, stderr: Vec<String>) -> Vec<String> {
+        for line in stderr {
+            if self.started {
+                stdout.push(line);
+            } else if strip_ansi(&line).trim_start().starts_with("Running `") {
+                self.started = true;
+            }
+        }
+        stdout.iter().map(|line| strip_ansi(line)).collect()
+    }
+}
+
+/// Whether something on localhost accepts connections on this port
+pub fn port_is_open(port: u16) -> bool {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpStream::connect_timeout(&address, Duration::from_millis(200)).is_ok()
+}
@@ -0,0 +1,103 @@
+use crate::tools::lockfile::compare_versions;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// Which part of a version to bump
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BumpLevel {
+    /// 1.2.3 → 2.0.0
+    Major,
+    /// 1.2.3 → 1.3.0
+    Minor,
+    /// 1.2.3 → 1.2.4
+    Patch,
+    /// 1.2.3 → 1.2.4-alpha.1, and 1.2.4-alpha.1 → 1.2.4-alpha.2
+    Prerelease,
+}
+
+/// A version split into its release numbers and pre-release; build metadata is dropped
+fn parse_version(version: &str) -> Result<([u64; 3], Option<&str>)> {
+    let invalid = || anyhow!("'{version}' is not a version of the form 1.2.3 or 1.2.3-beta.1");
+    let version = version.split('+').next().unwrap_or(version);
+    let (release, pre) = match version.split_once('-') {
+        Some((release, pre)) if !pre.is_empty() => (release, Some(pre)),
+        Some(_) => return Err(invalid()),
+        None => (version, None),
+    };
+    let numbers: Vec<u64> = release
+        .split('.')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_>>()?;
+    let numbers: [u64; 3] = numbers.try_into().map_err(|_| invalid())?;
+    Ok((numbers, pre))
+}
+
+/// The next version at `level`. Bumping a pre-release to the release it leads up to gives
+/// that release, so 2.0.0-rc.1 becomes 2.0.0 with a major bump.
+pub fn bump_version(version: &str, level: BumpLevel) -> Result<String> {
+    let ([major, minor, patch], pre) = parse_version(version)?;
+    let released = pre.is_none();
+    let next = match level {
+        BumpLevel::Major if released || minor > 0 || patch > 0 => format!("{}.0.0", major + 1),
+        BumpLevel::Minor if released || patch > 0 => format!("{major}.{}.0", minor + 1),
+        BumpLevel::Patch if released => format!("{major}.{minor}.{}", patch + 1),
+        BumpLevel::Major | BumpLevel::Minor | BumpLevel::Patch => {
+            format!("{major}.{minor}.{patch}")
+        }
+        BumpLevel::Prerelease => match pre {
+            None => format!("{major}.{minor}.{}-alpha.1", patch + 1),
+            Some(pre) => {
+                // Count up the last numeric identifier, or start counting
+                let counter = pre
+                    .rsplit_once('.')
+                    .and_then(|(label, number)| Some((label, number.parse::<u64>().ok()?)));
+                let next_pre = match counter {
+                    Some((label, number)) => format!("{label}.{}", number + 1),
+                    None => format!("{pre}.1"),
+                };
+                format!("{major}.{minor}.{patch}-{next_pre}")
+            }
+        },
+    };
+    Ok(next)
+}
+
+/// Check an explicitly given version, which must be newer than the current one
+pub fn check_new_version(current: &str, new: &str) -> Result<()> {
+    parse_version(new)?;
+    if compare_versions(new, current).is_le() {
+        return Err(anyhow!(
+            "{new} is not newer than the current version {current}"
+        ));
+    }
+    Ok(())
+}
+
+/// A dependency requirement pointed at `version`, keeping its `^`, `=` or `~` operator;
+/// `None` for requirements like `>=1, <3` that aren't written as a single version
+pub fn update_requirement(requirement: &str, version: &str) -> Option<String> {
+    let requirement = requirement.trim();
+    let (operator, rest) = match requirement.find(|c: char| c.is_ascii_digit()) {
+        Some(start) => requirement.split_at(start),
+        None => return None,
+    };
+    let operator = operator.trim();
+    if !matches!(operator, "" | "^" | "=" | "~") || !is_single_version(rest) {
+        return None;
+    }
+    Some(format!("{operator}{version}"))
+}
+
+/// Whether `requirement` is a version with up to three numbers, like `1`, `1.2` or
+/// `1.2.3-rc.1`
+fn is_single_version(requirement: &str) -> bool {
+    let release = requirement.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = release.split('.').collect();
+    parts.len() <= 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
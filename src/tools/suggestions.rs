@@ -0,0 +1,116 @@
+use crate::tools::diagnostics::SuggestionEdit;
+use anyhow::{Result, anyhow};
+use std::path::{Component, Path, PathBuf};
+
+/// Source file contents before and after edits
+pub struct EditedFile {
+    pub path: PathBuf,
+    pub original: String,
+    pub edits: Vec<SuggestionEdit>,
+}
+
+impl EditedFile {
+    pub fn new(path: PathBuf, original: String) -> Self {
+        Self {
+            path,
+            original,
+            edits: vec![],
+        }
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let original = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Could not read {}: {e}", path.display()))?;
+        Ok(Self::new(path, original))
+    }
+
+    /// Check that an edit still lines up with the file as it is on disk and doesn't
+    /// collide with an edit that has already been accepted
+    pub fn check(&self, edit: &SuggestionEdit) -> Result<(), String> {
+        let content = &self.original;
+        if edit.byte_end > content.len()
+            || edit.byte_start > edit.byte_end
+            || !content.is_char_boundary(edit.byte_start)
+            || !content.is_char_boundary(edit.byte_end)
+        {
+            return Err(format!("{} has changed since it was checked", edit.file));
+        }
+
+        let before = &content[..edit.byte_start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        if (line, column) != (edit.line, edit.column) {
+            return Err(format!("{} has changed since it was checked", edit.file));
+        }
+
+        if self.edits.iter().any(|accepted| {
+            edit.byte_start < accepted.byte_end && accepted.byte_start < edit.byte_end
+        }) {
+            return Err("overlaps with another selected suggestion".into());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_duplicate(&self, edit: &SuggestionEdit) -> bool {
+        self.edits.iter().any(|accepted| {
+            accepted.byte_start == edit.byte_start
+                && accepted.byte_end == edit.byte_end
+                && accepted.replacement == edit.replacement
+        })
+    }
+
+    /// Translate an edit that was not applied so that it refers to the same text in the
+    /// edited file. Returns None if an applied edit touched the text it would replace.
+    pub fn rebase(&self, edit: &SuggestionEdit, edited: &str) -> Option<SuggestionEdit> {
+        let mut shift: isize = 0;
+        for applied in &self.edits {
+            if edit.byte_start < applied.byte_end && applied.byte_start < edit.byte_end
+                || applied.byte_start == edit.byte_start
+            {
+                return None;
+            }
+            if applied.byte_end <= edit.byte_start {
+                shift += applied.replacement.len() as isize
+                    - (applied.byte_end - applied.byte_start) as isize;
+            }
+        }
+
+        let byte_start = edit.byte_start.checked_add_signed(shift)?;
+        let byte_end = edit.byte_end.checked_add_signed(shift)?;
+        let before = edited.get(..byte_start)?;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        Some(SuggestionEdit {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            byte_start,
+            byte_end,
+            ..edit.clone()
+        })
+    }
+
+    pub fn edited(&self) -> String {
+        let mut content = self.original.clone();
+        let mut edits: Vec<_> = self.edits.iter().collect();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.byte_start));
+        for edit in edits {
+            content.replace_range(edit.byte_start..edit.byte_end, &edit.replacement);
+        }
+        content
+    }
+}
+
+/// The path of a file named in a diagnostic, which must be inside the workspace
+pub fn resolve(root: &Path, file: &str) -> Result<PathBuf, String> {
+    let path = root.join(file);
+    let escapes = Path::new(file)
+        .components()
+        .any(|component| matches!(component, Component::ParentDir));
+    if path.starts_with(root) && !escapes {
+        Ok(path)
+    } else {
+        Err(format!("{file} is outside the workspace"))
+    }
+}
@@ -0,0 +1,362 @@
+use crate::tools::diagnostics::plural;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Outcome of a single test
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// A single test result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestOutcome {
+    pub name: String,
+    /// The test target this test belongs to (e.g. "unittests src/lib.rs", "tests/it.rs")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub status: TestStatus,
+    /// Execution time, only reported by libtest's JSON format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    /// `file:line:column` of the panic that failed the test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panic_location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panic_message: Option<String>,
+    /// Reason given for an ignored test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_reason: Option<String>,
+    /// Captured stdout/stderr of a failed test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl TestOutcome {
+    fn new(name: &str, target: Option<&String>, status: TestStatus) -> Self {
+        Self {
+            name: name.to_string(),
+            target: target.cloned(),
+            status,
+            duration_secs: None,
+            panic_location: None,
+            panic_message: None,
+            ignore_reason: None,
+            output: None,
+        }
+    }
+
+    fn set_output(&mut self, output: &str) {
+        let output = output.trim_matches('\n');
+        if let Some((location, message)) = parse_panic(output) {
+            self.panic_location = Some(location);
+            self.panic_message = Some(message);
+        }
+        self.output = Some(output.to_string());
+    }
+}
+
+/// All test results from one cargo test invocation
+#[derive(Debug, Default)]
+pub struct TestReport {
+    pub tests: Vec<TestOutcome>,
+    /// Number of test binaries (including doctests) that ran
+    pub suites: usize,
+}
+
+impl TestReport {
+    pub fn count(&self, status: TestStatus) -> usize {
+        self.tests.iter().filter(|t| t.status == status).count()
+    }
+
+    fn with_status(&self, status: TestStatus) -> impl Iterator<Item = &TestOutcome> {
+        self.tests.iter().filter(move |t| t.status == status)
+    }
+}
+
+/// A line of libtest's unstable `--format json` output
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum LibtestEvent {
+    Suite {
+        event: String,
+    },
+    Test {
+        event: String,
+        name: String,
+        exec_time: Option<f64>,
+        stdout: Option<String>,
+        message: Option<String>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Test targets in the order cargo ran them, from the `Running ...` and `Doc-tests ...`
+/// lines cargo prints to stderr. libtest prints one suite to stdout per target, in the
+/// same order.
+fn parse_targets(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .map(str::trim_start)
+        .filter_map(|line| {
+            if let Some(target) = line.strip_prefix("Running ") {
+                // Drop the path to the test binary: "unittests src/lib.rs (target/debug/...)"
+                Some(match target.rsplit_once(" (") {
+                    Some((target, _)) => target.to_string(),
+                    None => target.to_string(),
+                })
+            } else {
+                line.starts_with("Doc-tests ").then(|| line.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Parse cargo test output, in either libtest's JSON format (nightly only) or the default
+/// human-readable format
+pub fn parse_test_output(stdout: &str, stderr: &str) -> TestReport {
+    let targets = parse_targets(stderr);
+    let is_json = stdout
+        .lines()
+        .any(|line| line.starts_with('{') && line.contains("\"type\""));
+
+    if is_json {
+        parse_json(stdout, &targets)
+    } else {
+        parse_text(stdout, &targets)
+    }
+}
+
+fn parse_json(stdout: &str, targets: &[String]) -> TestReport {
+    let mut report = TestReport::default();
+
+    for line in stdout.lines().filter(|line| line.starts_with('{')) {
+        let Ok(event) = serde_json::from_str::<LibtestEvent>(line) else {
+            continue;
+        };
+
+        match event {
+            LibtestEvent::Suite { event } if event == "started" => report.suites += 1,
+            LibtestEvent::Test {
+                event,
+                name,
+                exec_time,
+                stdout,
+                message,
+            } => {
+                let status = match event.as_str() {
+                    "ok" => TestStatus::Passed,
+                    "failed" | "timeout" => TestStatus::Failed,
+                    "ignored" => TestStatus::Ignored,
+                    _ => continue,
+                };
+                let target = report.suites.checked_sub(1).and_then(|i| targets.get(i));
+                let mut outcome = TestOutcome::new(&name, target, status);
+                outcome.duration_secs = exec_time;
+                match status {
+                    TestStatus::Failed => outcome.set_output(&stdout.unwrap_or_default()),
+                    TestStatus::Ignored => outcome.ignore_reason = message,
+                    TestStatus::Passed => {}
+                }
+                report.tests.push(outcome);
+            }
+            _ => {}
+        }
+    }
+
+    report
+}
+
+fn parse_text(stdout: &str, targets: &[String]) -> TestReport {
+    let mut report = TestReport::default();
+    // Index into report.tests where the current suite starts
+    let mut suite_start = 0;
+    // Name and captured lines of the failure currently being read
+    let mut failure: Option<(String, Vec<&str>)> = None;
+
+    for line in stdout.lines() {
+        if line.starts_with("running ") && (line.ends_with(" test") || line.ends_with(" tests")) {
+            finish_failure(&mut report.tests[suite_start..], failure.take());
+            report.suites += 1;
+            suite_start = report.tests.len();
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            finish_failure(&mut report.tests[suite_start..], failure.take());
+            failure = Some((name.to_string(), vec![]));
+        } else if line == "failures:" || line.starts_with("test result: ") {
+            finish_failure(&mut report.tests[suite_start..], failure.take());
+        } else if let Some((_, lines)) = &mut failure {
+            lines.push(line);
+        } else if let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.rsplit_once(" ... "))
+        {
+            let (status, ignore_reason) = match result {
+                "ok" => (TestStatus::Passed, None),
+                "FAILED" => (TestStatus::Failed, None),
+                "ignored" => (TestStatus::Ignored, None),
+                other => match other.strip_prefix("ignored, ") {
+                    Some(reason) => (TestStatus::Ignored, Some(reason.to_string())),
+                    None => continue,
+                },
+            };
+            let name = name.strip_suffix(" - should panic").unwrap_or(name);
+            let target = report.suites.checked_sub(1).and_then(|i| targets.get(i));
+            let mut outcome = TestOutcome::new(name, target, status);
+            outcome.ignore_reason = ignore_reason;
+            report.tests.push(outcome);
+        }
+    }
+    finish_failure(&mut report.tests[suite_start..], failure.take());
+
+    report
+}
+
+/// Attach a failure's captured output to the test it belongs to
+fn finish_failure(suite: &mut [TestOutcome], failure: Option<(String, Vec<&str>)>) {
+    let Some((name, lines)) = failure else {
+        return;
+    };
+
+    if let Some(test) = suite.iter_mut().find(|test| test.name == name) {
+        test.set_output(&lines.join("\n"));
+    }
+}
+
+/// What a failed test printed before it panicked, or all of its output if it failed
+/// without a panic
+fn printed_output(output: &str) -> &str {
+    let Some(panic) = output.find("panicked at ") else {
+        return output;
+    };
+    match output[..panic].rfind("thread '") {
+        Some(start) => output[..start].trim_end(),
+        None => output,
+    }
+}
+
+/// Extract the location and message of a panic from captured test output.
+///
+/// Handles both the current format
+/// `thread 'name' panicked at src/lib.rs:13:43:\nmessage`
+/// and the pre-1.73 format
+/// `thread 'name' panicked at 'message', src/lib.rs:13:43`.
+fn parse_panic(output: &str) -> Option<(String, String)> {
+    let start = output.find("panicked at ")? + "panicked at ".len();
+    let rest = &output[start..];
+    let (first_line, following) = rest.split_once('\n').unwrap_or((rest, ""));
+
+    if let Some(quoted) = first_line.strip_prefix('\'') {
+        let (message, location) = quoted.rsplit_once("', ")?;
+        return Some((location.to_string(), message.to_string()));
+    }
+
+    let location = first_line.strip_suffix(':').unwrap_or(first_line);
+    let message = following
+        .lines()
+        .take_while(|line| {
+            !line.is_empty() && !line.starts_with("stack backtrace:") && !line.starts_with("note:")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some((location.to_string(), message))
+}
+
+/// Render a test report: counts, the names of failed and ignored tests, and each failure's
+/// panic and captured output, optionally followed by one JSON object per reported test
+pub fn render_test_report(report: &TestReport, include_passed: bool, json: bool) -> String {
+    if report.suites == 0 {
+        return "🧪 No tests were run\n\n".into();
+    }
+
+    let failed: Vec<_> = report.with_status(TestStatus::Failed).collect();
+    let ignored: Vec<_> = report.with_status(TestStatus::Ignored).collect();
+
+    let mut out = format!(
+        "🧪 {} passed, {} failed, {} ignored ({})\n\n",
+        report.count(TestStatus::Passed),
+        failed.len(),
+        ignored.len(),
+        plural(report.suites, "test target", "test targets"),
+    );
+
+    if !failed.is_empty() {
+        let names = failed.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        let _ = writeln!(out, "❌ {} failed: {}\n", failed.len(), names.join(", "));
+
+        for test in &failed {
+            let _ = write!(out, "── {}", test.name);
+            if let Some(target) = &test.target {
+                let _ = write!(out, " [{target}]");
+            }
+            if let Some(duration) = test.duration_secs {
+                let _ = write!(out, " ({duration:.2}s)");
+            }
+            out.push_str(" ──\n");
+            if let Some(location) = &test.panic_location {
+                let _ = writeln!(out, "💥 panicked at {location}");
+            }
+            if let Some(message) = &test.panic_message {
+                let _ = writeln!(out, "{message}");
+            }
+            let printed = test
+                .output
+                .as_deref()
+                .map(printed_output)
+                .unwrap_or_default();
+            if !printed.is_empty() {
+                let _ = writeln!(out, "📤 Output:\n{printed}");
+            }
+            out.push('\n');
+        }
+    }
+
+    if !ignored.is_empty() {
+        let names = ignored
+            .iter()
+            .map(|test| match &test.ignore_reason {
+                Some(reason) => format!("{} ({reason})", test.name),
+                None => test.name.clone(),
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "⏭️  {} ignored: {}\n", ignored.len(), names.join(", "));
+    }
+
+    if include_passed {
+        let passed = report
+            .with_status(TestStatus::Passed)
+            .map(|test| match test.duration_secs {
+                Some(duration) => format!("{} ({duration:.2}s)", test.name),
+                None => test.name.clone(),
+            })
+            .collect::<Vec<_>>();
+        if !passed.is_empty() {
+            let _ = writeln!(out, "✅ {} passed: {}\n", passed.len(), passed.join(", "));
+        }
+    }
+
+    let listed: Vec<_> = report
+        .tests
+        .iter()
+        .filter(|test| include_passed || test.status != TestStatus::Passed)
+        .collect();
+    if json && !listed.is_empty() {
+        out.push_str("📋 Tests (JSON, one per line):\n");
+        for test in listed {
+            if let Ok(json) = serde_json::to_string(test) {
+                out.push_str(&json);
+                out.push('\n');
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}